@@ -0,0 +1,481 @@
+//! Example contract renting out the NFTs it holds through the chain extension of the runtime.
+//!
+//! The contract is the lender of its rentals: the owner lists a token with its fee and longest
+//! rental, the borrower signs an order on these terms naming the contract as lender and calls
+//! `rent`. The rent is paid to the contract and its owner can claim it.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use ink_env::{AccountId, DefaultEnvironment, Environment};
+use ink_lang as ink;
+use ink_prelude::vec::Vec;
+use ink_storage::traits::{PackedLayout, SpreadLayout};
+
+/// Rental as reported by the chain extension.
+#[derive(Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub struct Rental {
+	pub lender: AccountId,
+	pub fee: u64,
+	pub due_date: u64,
+	pub asset_id: Option<u32>,
+}
+
+/// Terms the marketplace rents a token out on.
+#[derive(
+	Debug, Clone, Copy, PartialEq, Eq, scale::Encode, scale::Decode, SpreadLayout, PackedLayout,
+)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo, ink_storage::traits::StorageLayout))]
+pub struct Listing {
+	/// Fee of the rental, in the smallest unit of the native currency.
+	pub fee: u64,
+	/// Longest rental in seconds.
+	pub max_duration: u64,
+}
+
+/// Track record of an account, see `pallet_renting::Reputation`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub struct Reputation {
+	pub completed: u32,
+	pub missed_repayments: u32,
+	pub early_stops: u32,
+	pub disputes_lost: u32,
+}
+
+impl Reputation {
+	pub fn score(&self) -> u32 {
+		self.completed
+			.saturating_sub(self.missed_repayments)
+			.saturating_sub(self.early_stops)
+			.saturating_sub(self.disputes_lost)
+	}
+
+	pub fn qualifies_for(&self, min_reputation: u32) -> bool {
+		min_reputation == 0
+			|| (self.missed_repayments == 0
+				&& self.disputes_lost == 0
+				&& self.score() >= min_reputation)
+	}
+}
+
+/// Calls of `runtime/src/chain_extension.rs`, extension id `0x4e52`.
+#[ink::chain_extension]
+pub trait NftRenting {
+	type ErrorCode = NftRentingError;
+
+	#[ink(extension = 0x4e52_0001, handle_status = false, returns_result = false)]
+	fn owner_of(token_id: Vec<u8>) -> Option<AccountId>;
+
+	#[ink(extension = 0x4e52_0002, handle_status = false, returns_result = false)]
+	fn custodian_of(token_id: Vec<u8>) -> Option<AccountId>;
+
+	#[ink(extension = 0x4e52_0003)]
+	fn transfer(to: AccountId, token_id: Vec<u8>) -> Result<(), NftRentingError>;
+
+	#[ink(extension = 0x4e52_0004)]
+	fn approve(to: AccountId, token_id: Vec<u8>) -> Result<(), NftRentingError>;
+
+	#[ink(extension = 0x4e52_0101)]
+	fn create_rental(
+		lender: AccountId,
+		borrower: AccountId,
+		message_left: Vec<u8>,
+		signature_left: Vec<u8>,
+		message_right: Vec<u8>,
+		signature_right: Vec<u8>,
+	) -> Result<(), NftRentingError>;
+
+	#[ink(extension = 0x4e52_0102)]
+	fn stop_renting(token_id: Vec<u8>) -> Result<(), NftRentingError>;
+
+	#[ink(extension = 0x4e52_0103, handle_status = false, returns_result = false)]
+	fn rental(borrower: AccountId, token_id: Vec<u8>) -> Option<Rental>;
+
+	#[ink(extension = 0x4e52_0104, handle_status = false, returns_result = false)]
+	fn reputation(account: AccountId) -> Reputation;
+
+	#[ink(extension = 0x4e52_0105, handle_status = false, returns_result = false)]
+	fn user_of(token_id: Vec<u8>) -> Option<AccountId>;
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub enum NftRentingError {
+	/// The runtime refused the call.
+	CallFailed,
+	/// The runtime answered with a status code this contract does not know.
+	UnknownStatusCode(u32),
+}
+
+impl ink_env::chain_extension::FromStatusCode for NftRentingError {
+	fn from_status_code(status_code: u32) -> Result<(), Self> {
+		match status_code {
+			0 => Ok(()),
+			1 => Err(Self::CallFailed),
+			code => Err(Self::UnknownStatusCode(code)),
+		}
+	}
+}
+
+/// Default environment extended with the NFT and renting calls.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub enum RentingEnvironment {}
+
+impl Environment for RentingEnvironment {
+	const MAX_EVENT_TOPICS: usize = <DefaultEnvironment as Environment>::MAX_EVENT_TOPICS;
+
+	type AccountId = <DefaultEnvironment as Environment>::AccountId;
+	type Balance = <DefaultEnvironment as Environment>::Balance;
+	type Hash = <DefaultEnvironment as Environment>::Hash;
+	type BlockNumber = <DefaultEnvironment as Environment>::BlockNumber;
+	type Timestamp = <DefaultEnvironment as Environment>::Timestamp;
+
+	type ChainExtension = NftRenting;
+}
+
+#[ink::contract(env = crate::RentingEnvironment)]
+mod rental_marketplace {
+	use super::{Listing, NftRentingError, Rental};
+	use ink_prelude::{format, string::String, vec::Vec};
+	use ink_storage::{traits::SpreadAllocate, Mapping};
+
+	#[ink(storage)]
+	#[derive(SpreadAllocate)]
+	pub struct RentalMarketplace {
+		owner: AccountId,
+		/// Reputation score borrowers without failures need to rent from the marketplace.
+		min_reputation: u32,
+		/// SS58 address of the marketplace, the lender of the offers it submits.
+		address: String,
+		/// Terms of the tokens the marketplace rents out.
+		listings: Mapping<Vec<u8>, Listing>,
+	}
+
+	#[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
+	#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+	pub enum Error {
+		/// Only the owner of the marketplace can do this.
+		NotOwner,
+		/// The marketplace does not own the token.
+		NotHeld,
+		/// The token is not listed for rent.
+		NotListed,
+		/// The reputation of the borrower is below the minimum of the marketplace.
+		ReputationTooLow,
+		/// The runtime refused the call.
+		CallFailed,
+		/// The balance of the marketplace is too low.
+		InsufficientBalance,
+	}
+
+	impl From<NftRentingError> for Error {
+		fn from(_: NftRentingError) -> Self {
+			Error::CallFailed
+		}
+	}
+
+	#[ink(event)]
+	pub struct Rented {
+		#[ink(topic)]
+		borrower: AccountId,
+		token_id: Vec<u8>,
+	}
+
+	impl RentalMarketplace {
+		#[ink(constructor)]
+		pub fn new(min_reputation: u32) -> Self {
+			ink_lang::utils::initialize_contract(|contract: &mut Self| {
+				contract.owner = Self::env().caller();
+				contract.min_reputation = min_reputation;
+			})
+		}
+
+		/// Rent a token of the marketplace. `message_right` is the order the caller signed on the
+		/// listed terms, the marketplace offers the token until the longest rental is over.
+		#[ink(message)]
+		pub fn rent(
+			&mut self,
+			token_id: Vec<u8>,
+			message_right: Vec<u8>,
+			signature_right: Vec<u8>,
+		) -> Result<(), Error> {
+			let borrower = self.env().caller();
+			let marketplace = self.env().account_id();
+			if self.env().extension().owner_of(token_id.clone()) != Some(marketplace) {
+				return Err(Error::NotHeld);
+			}
+			let listing = self.listings.get(&token_id).ok_or(Error::NotListed)?;
+			if !self.env().extension().reputation(borrower).qualifies_for(self.min_reputation) {
+				return Err(Error::ReputationTooLow);
+			}
+			// the marketplace submits the match, so its own order needs no signature
+			let due_date = self.env().block_timestamp() / 1000 + listing.max_duration;
+			self.env().extension().create_rental(
+				marketplace,
+				borrower,
+				self.offer(&token_id, listing, due_date),
+				Vec::new(),
+				message_right,
+				signature_right,
+			)?;
+			self.env().emit_event(Rented { borrower, token_id });
+			Ok(())
+		}
+
+		/// Rent out `token_id` on `listing` from now on.
+		#[ink(message)]
+		pub fn list(&mut self, token_id: Vec<u8>, listing: Listing) -> Result<(), Error> {
+			self.ensure_owner()?;
+			self.listings.insert(&token_id, &listing);
+			Ok(())
+		}
+
+		/// Stop renting out `token_id`, ongoing rentals run until their due date.
+		#[ink(message)]
+		pub fn unlist(&mut self, token_id: Vec<u8>) -> Result<(), Error> {
+			self.ensure_owner()?;
+			self.listings.remove(&token_id);
+			Ok(())
+		}
+
+		/// Set the SS58 address the offers of the marketplace name it by. The runtime refuses
+		/// offers whose lender is not the marketplace, so a wrong address only fails rentals.
+		#[ink(message)]
+		pub fn set_address(&mut self, address: String) -> Result<(), Error> {
+			self.ensure_owner()?;
+			self.address = address;
+			Ok(())
+		}
+
+		/// Terms `token_id` is rented out on.
+		#[ink(message)]
+		pub fn listing(&self, token_id: Vec<u8>) -> Option<Listing> {
+			self.listings.get(&token_id)
+		}
+
+		/// Ongoing rental of `token_id` by `borrower`.
+		#[ink(message)]
+		pub fn rental(&self, borrower: AccountId, token_id: Vec<u8>) -> Option<Rental> {
+			self.env().extension().rental(borrower, token_id)
+		}
+
+		/// Account holding `token_id`, the borrower while it is rented out.
+		#[ink(message)]
+		pub fn custodian_of(&self, token_id: Vec<u8>) -> Option<AccountId> {
+			self.env().extension().custodian_of(token_id)
+		}
+
+		/// Account allowed to use `token_id` now, the borrower until the rental is due.
+		#[ink(message)]
+		pub fn user_of(&self, token_id: Vec<u8>) -> Option<AccountId> {
+			self.env().extension().user_of(token_id)
+		}
+
+		/// Let `operator` manage a token of the marketplace.
+		#[ink(message)]
+		pub fn approve(&mut self, operator: AccountId, token_id: Vec<u8>) -> Result<(), Error> {
+			self.ensure_owner()?;
+			Ok(self.env().extension().approve(operator, token_id)?)
+		}
+
+		/// Hand a token of the marketplace over to `to`.
+		#[ink(message)]
+		pub fn withdraw(&mut self, to: AccountId, token_id: Vec<u8>) -> Result<(), Error> {
+			self.ensure_owner()?;
+			Ok(self.env().extension().transfer(to, token_id)?)
+		}
+
+		/// Pay `amount` of the collected rent to the owner.
+		#[ink(message)]
+		pub fn claim(&mut self, amount: Balance) -> Result<(), Error> {
+			self.ensure_owner()?;
+			self.env().transfer(self.owner, amount).map_err(|_| Error::InsufficientBalance)
+		}
+
+		/// Offer of the marketplace renting out `token_id` on `listing` until `due_date`.
+		fn offer(&self, token_id: &[u8], listing: Listing, due_date: u64) -> Vec<u8> {
+			let token = token_id.iter().map(|b| format!("{:02x}", b)).collect::<String>();
+			format!(
+				"{{\"lender\":\"{}\",\"fee\":{},\"token\":\"{}\",\"due_date\":{}}}",
+				self.address, listing.fee, token, due_date
+			)
+			.into_bytes()
+		}
+
+		fn ensure_owner(&self) -> Result<(), Error> {
+			if self.env().caller() != self.owner {
+				return Err(Error::NotOwner);
+			}
+			Ok(())
+		}
+	}
+
+	#[cfg(test)]
+	mod tests {
+		use super::*;
+		use crate::Reputation;
+		use ink_env::{test, DefaultEnvironment};
+		use ink_lang as ink;
+		use scale::{Decode, Encode};
+
+		/// Extension function answering `output` with `status`, after `check` looked at the input.
+		struct MockFunction {
+			func_id: u32,
+			status: u32,
+			output: Vec<u8>,
+			check: fn(&[u8]),
+		}
+
+		impl test::ChainExtension for MockFunction {
+			fn func_id(&self) -> u32 {
+				self.func_id
+			}
+
+			fn call(&mut self, input: &[u8], output: &mut Vec<u8>) -> u32 {
+				(self.check)(input);
+				output.extend_from_slice(&self.output);
+				self.status
+			}
+		}
+
+		fn mock(func_id: u32, status: u32, output: impl Encode, check: fn(&[u8])) {
+			test::register_chain_extension(MockFunction {
+				func_id,
+				status,
+				output: output.encode(),
+				check,
+			});
+		}
+
+		fn accounts() -> test::DefaultAccounts<DefaultEnvironment> {
+			test::default_accounts::<DefaultEnvironment>()
+		}
+
+		fn marketplace_id() -> AccountId {
+			ink_env::account_id::<DefaultEnvironment>()
+		}
+
+		/// The marketplace holds the token and `reputation` is the track record of every account.
+		fn chain(reputation: Reputation) {
+			mock(0x4e52_0001, 0, Some(marketplace_id()), |_| {});
+			mock(0x4e52_0104, 0, reputation, |_| {});
+		}
+
+		/// Marketplace renting out token 7 for a day at most.
+		fn listed(min_reputation: u32) -> RentalMarketplace {
+			let mut marketplace = RentalMarketplace::new(min_reputation);
+			assert_eq!(marketplace.set_address("5Market".into()), Ok(()));
+			let listing = Listing { fee: 1_000, max_duration: 86_400 };
+			assert_eq!(marketplace.list(vec![7], listing), Ok(()));
+			test::set_block_timestamp::<DefaultEnvironment>(1_700_000_000_000);
+			marketplace
+		}
+
+		fn rent_as_bob(marketplace: &mut RentalMarketplace) -> Result<(), Error> {
+			test::set_caller::<DefaultEnvironment>(accounts().bob);
+			marketplace.rent(vec![7], b"right".to_vec(), vec![1; 64])
+		}
+
+		#[ink::test]
+		fn rent_submits_the_listed_terms_as_lender() {
+			let mut marketplace = listed(0);
+			chain(Reputation::default());
+			mock(0x4e52_0101, 0, (), |input| {
+				let (lender, borrower, left, signature_left, right, signature_right) =
+					<(AccountId, AccountId, Vec<u8>, Vec<u8>, Vec<u8>, Vec<u8>)>::decode(
+						&mut &input[..],
+					)
+					.unwrap();
+				assert_eq!(lender, marketplace_id());
+				assert_eq!(borrower, accounts().bob);
+				let offer =
+					br#"{"lender":"5Market","fee":1000,"token":"07","due_date":1700086400}"#;
+				assert_eq!((left, signature_left), (offer.to_vec(), vec![]));
+				assert_eq!((right, signature_right), (b"right".to_vec(), vec![1; 64]));
+			});
+
+			assert_eq!(rent_as_bob(&mut marketplace), Ok(()));
+			assert_eq!(test::recorded_events().count(), 1);
+		}
+
+		#[ink::test]
+		fn rent_requires_a_token_of_the_marketplace() {
+			let mut marketplace = listed(0);
+			mock(0x4e52_0001, 0, Some(accounts().alice), |_| {});
+			assert_eq!(rent_as_bob(&mut marketplace), Err(Error::NotHeld));
+		}
+
+		#[ink::test]
+		fn rent_requires_a_listed_token() {
+			let mut marketplace = listed(0);
+			chain(Reputation::default());
+			// a forged offer would never reach the runtime, nothing is submitted
+			mock(0x4e52_0101, 0, (), |_| panic!("unlisted token submitted"));
+			assert_eq!(marketplace.unlist(vec![7]), Ok(()));
+			assert_eq!(rent_as_bob(&mut marketplace), Err(Error::NotListed));
+		}
+
+		#[ink::test]
+		fn rent_requires_the_minimum_reputation() {
+			let mut marketplace = listed(2);
+			chain(Reputation { completed: 2, early_stops: 1, ..Default::default() });
+			assert_eq!(rent_as_bob(&mut marketplace), Err(Error::ReputationTooLow));
+			chain(Reputation { completed: 5, missed_repayments: 1, ..Default::default() });
+			assert_eq!(rent_as_bob(&mut marketplace), Err(Error::ReputationTooLow));
+		}
+
+		#[ink::test]
+		fn refused_rental_is_reported() {
+			let mut marketplace = listed(0);
+			chain(Reputation::default());
+			mock(0x4e52_0101, 1, (), |_| {});
+			assert_eq!(rent_as_bob(&mut marketplace), Err(Error::CallFailed));
+			assert_eq!(test::recorded_events().count(), 0);
+		}
+
+		#[ink::test]
+		fn unknown_status_codes_do_not_trap() {
+			let mut marketplace = listed(0);
+			chain(Reputation::default());
+			mock(0x4e52_0101, 7, (), |_| {});
+			assert_eq!(rent_as_bob(&mut marketplace), Err(Error::CallFailed));
+		}
+
+		#[ink::test]
+		fn rental_queries_are_forwarded() {
+			let marketplace = RentalMarketplace::new(0);
+			let rental = Rental {
+				lender: marketplace_id(),
+				fee: 1_000,
+				due_date: 1_700_000_000,
+				asset_id: None,
+			};
+			mock(0x4e52_0103, 0, Some(rental.clone()), |_| {});
+			mock(0x4e52_0002, 0, Some(accounts().bob), |_| {});
+			mock(0x4e52_0105, 0, Some(accounts().bob), |_| {});
+			assert_eq!(marketplace.rental(accounts().bob, vec![7]), Some(rental));
+			assert_eq!(marketplace.custodian_of(vec![7]), Some(accounts().bob));
+			assert_eq!(marketplace.user_of(vec![7]), Some(accounts().bob));
+		}
+
+		#[ink::test]
+		fn only_the_owner_manages_the_tokens() {
+			let mut marketplace = RentalMarketplace::new(0);
+			mock(0x4e52_0003, 0, (), |_| {});
+			mock(0x4e52_0004, 0, (), |_| {});
+			assert_eq!(marketplace.withdraw(accounts().alice, vec![7]), Ok(()));
+			assert_eq!(marketplace.approve(accounts().charlie, vec![7]), Ok(()));
+
+			test::set_caller::<DefaultEnvironment>(accounts().bob);
+			assert_eq!(marketplace.withdraw(accounts().bob, vec![7]), Err(Error::NotOwner));
+			assert_eq!(marketplace.approve(accounts().bob, vec![7]), Err(Error::NotOwner));
+			assert_eq!(marketplace.claim(1), Err(Error::NotOwner));
+			let listing = Listing { fee: 0, max_duration: u64::MAX };
+			assert_eq!(marketplace.list(vec![7], listing), Err(Error::NotOwner));
+			assert_eq!(marketplace.unlist(vec![7]), Err(Error::NotOwner));
+			assert_eq!(marketplace.set_address("5Bob".into()), Err(Error::NotOwner));
+		}
+	}
+}
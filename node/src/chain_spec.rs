@@ -1,6 +1,6 @@
 use node_template_runtime::{
-	AccountId, AuraConfig, BalancesConfig, GenesisConfig, GrandpaConfig, Signature, SudoConfig,
-	SystemConfig, WASM_BINARY,
+	AccountId, AuraConfig, BalancesConfig, GenesisConfig, GrandpaConfig, MetadataVerifiersConfig,
+	Signature, SudoConfig, SystemConfig, WASM_BINARY,
 };
 use sc_service::ChainType;
 use sp_consensus_aura::sr25519::AuthorityId as AuraId;
@@ -149,9 +149,18 @@ fn testnet_genesis(
 		},
 		sudo: SudoConfig {
 			// Assign network admin rights.
-			key: Some(root_key),
+			key: Some(root_key.clone()),
 		},
 		transaction_payment: Default::default(),
 		assets: Default::default(),
+		// governance starts without a council, the sudo key elects one with `set_members`
+		council: Default::default(),
+		democracy: Default::default(),
+		treasury: Default::default(),
+		// the sudo key verifies metadata until governance elects other verifiers
+		metadata_verifiers: MetadataVerifiersConfig {
+			members: vec![root_key].try_into().expect("a single verifier is within the bound"),
+			phantom: Default::default(),
+		},
 	}
 }
@@ -50,7 +50,7 @@ benchmarks! {
 		let token_id = ListOwned::<T>::get(acc1.clone())[0].to_vec();
 	}: approve(RawOrigin::Signed(acc1.clone()), acc2.clone(), token_id.clone())
 	verify{
-		assert_eq!(TokenApproval::<T>::get(token_id).len(),1);
+		assert!(TokenApproval::<T>::get(token_id).contains(&acc2));
 	}
 
 	approve_for_all{
@@ -66,10 +66,51 @@ benchmarks! {
 		let uri = "linkUri".as_bytes().to_vec();
 		NFTCurrency::<T>::mint_to(RawOrigin::Signed(acc1.clone()).into(),acc1.clone(),uri);
 		let token_id = ListOwned::<T>::get(acc1.clone())[0].to_vec();
-		let token_uri = "ipfs".as_bytes().to_vec();
-	}: set_token_uri(RawOrigin::Signed(acc1.clone()),token_id.clone(),token_uri.clone())
+		let cid = Cid::V1 {
+			codec: cid::RAW,
+			hash: cid::Multihash { code: cid::SHA2_256, digest: sp_io::hashing::sha2_256(b"").to_vec() },
+		};
+		let token_uri = [&b"ipfs://"[..], &cid.to_text()].concat();
+	}: set_token_uri(RawOrigin::Signed(acc1.clone()),token_id.clone(),token_uri.clone(),Some(cid.clone()))
 	verify{
-		assert_eq!(TokenUri::<T>::get(token_id), Some(token_uri));
+		assert_eq!(TokenUri::<T>::get(token_id.clone()), Some(token_uri));
+		assert_eq!(TokenCid::<T>::get(token_id), Some(cid));
+	}
+
+	lock_collection{
+		let origin = T::AdminOrigin::successful_origin();
+	}: lock_collection<T::RuntimeOrigin>(origin)
+	verify{
+		assert!(CollectionLocked::<T>::get());
+	}
+
+	add_minter{
+		let minter: T::AccountId = account("minter",0,0);
+		let origin = T::AdminOrigin::successful_origin();
+	}: add_minter<T::RuntimeOrigin>(origin, minter.clone())
+	verify{
+		assert!(Minters::<T>::contains_key(minter));
+	}
+
+	remove_minter{
+		let minter: T::AccountId = account("minter",0,0);
+		Minters::<T>::insert(&minter, ());
+		let origin = T::AdminOrigin::successful_origin();
+	}: remove_minter<T::RuntimeOrigin>(origin, minter.clone())
+	verify{
+		assert!(!Minters::<T>::contains_key(minter));
+	}
+
+	report_metadata{
+		let acc1: T::AccountId =  account("account1",0,0);
+		let uri = "https://example.com/1.json".as_bytes().to_vec();
+		NFTCurrency::<T>::mint_to(RawOrigin::Signed(acc1.clone()).into(),acc1.clone(),uri.clone());
+		let token_id = ListOwned::<T>::get(acc1)[0].to_vec();
+		let uri_hash = sp_io::hashing::blake2_256(&uri);
+		let origin = T::VerifierOrigin::successful_origin();
+	}: report_metadata<T::RuntimeOrigin>(origin, token_id.clone(), uri_hash, Verdict::Valid([1u8; 32]))
+	verify{
+		assert_eq!(MetadataChecks::<T>::get(token_id).unwrap().status, MetadataStatus::Valid);
 	}
 
 	impl_benchmark_test_suite!(NFTCurrency, crate::mock::new_test_ext(), crate::mock::Test);
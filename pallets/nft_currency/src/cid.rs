@@ -0,0 +1,170 @@
+//! IPFS content identifiers, the typed form of the uri of content addressed metadata.
+//!
+//! A CIDv0 is the sha2-256 multihash of a dag-pb node, written in base58btc. A CIDv1 also names
+//! the codec of the content and is written in base32 with the `b` multibase prefix. Only the
+//! digest of a `raw` CIDv1 is the hash of the file itself, other CIDs hash the node wrapping it.
+
+use codec::{Decode, Encode};
+use scale_info::TypeInfo;
+use sp_std::{iter, vec::Vec};
+
+/// Multihash code of sha2-256.
+pub const SHA2_256: u64 = 0x12;
+/// Multihash code of blake2b-256.
+pub const BLAKE2B_256: u64 = 0xb220;
+/// Multicodec of raw bytes.
+pub const RAW: u64 = 0x55;
+/// Multicodec of a merkle dag node, the codec of every CIDv0.
+pub const DAG_PB: u64 = 0x70;
+
+const BASE58: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+const BASE32: &[u8; 32] = b"abcdefghijklmnopqrstuvwxyz234567";
+
+/// Digest of some content with the hash function `code`.
+#[derive(Clone, Encode, Decode, PartialEq, Eq, TypeInfo, Debug)]
+pub struct Multihash {
+	pub code: u64,
+	pub digest: Vec<u8>,
+}
+
+impl Multihash {
+	/// Whether the hash function is supported and the digest has its length.
+	pub fn is_valid(&self) -> bool {
+		matches!(self.code, SHA2_256 | BLAKE2B_256) && self.digest.len() == 32
+	}
+
+	/// Whether `content` hashes to this digest.
+	pub fn matches(&self, content: &[u8]) -> bool {
+		match self.code {
+			SHA2_256 => sp_io::hashing::sha2_256(content)[..] == self.digest[..],
+			BLAKE2B_256 => sp_io::hashing::blake2_256(content)[..] == self.digest[..],
+			_ => false,
+		}
+	}
+
+	fn write(&self, out: &mut Vec<u8>) {
+		put_varint(self.code, out);
+		put_varint(self.digest.len() as u64, out);
+		out.extend_from_slice(&self.digest);
+	}
+}
+
+/// Content identifier of IPFS.
+#[derive(Clone, Encode, Decode, PartialEq, Eq, TypeInfo, Debug)]
+pub enum Cid {
+	V0(Multihash),
+	V1 { codec: u64, hash: Multihash },
+}
+
+impl Cid {
+	/// Whether the multihash is supported, and sha2-256 for a CIDv0.
+	pub fn is_valid(&self) -> bool {
+		match self {
+			Cid::V0(hash) => hash.code == SHA2_256 && hash.is_valid(),
+			Cid::V1 { hash, .. } => hash.is_valid(),
+		}
+	}
+
+	pub fn hash(&self) -> &Multihash {
+		match self {
+			Cid::V0(hash) | Cid::V1 { hash, .. } => hash,
+		}
+	}
+
+	/// Binary form of the cid.
+	pub fn to_bytes(&self) -> Vec<u8> {
+		let mut out = Vec::new();
+		match self {
+			Cid::V0(hash) => hash.write(&mut out),
+			Cid::V1 { codec, hash } => {
+				put_varint(1, &mut out);
+				put_varint(*codec, &mut out);
+				hash.write(&mut out);
+			},
+		}
+		out
+	}
+
+	/// Text form of the cid, as found in uris.
+	pub fn to_text(&self) -> Vec<u8> {
+		match self {
+			Cid::V0(_) => base58(&self.to_bytes()),
+			Cid::V1 { .. } => iter::once(b'b').chain(base32(&self.to_bytes())).collect(),
+		}
+	}
+
+	/// Whether `uri` names the content of the cid, either as `ipfs://<cid>` or through a gateway
+	/// as `.../ipfs/<cid>`, possibly followed by a path inside it.
+	pub fn is_named_by(&self, uri: &[u8]) -> bool {
+		let text = self.to_text();
+		(0..uri.len()).any(|start| {
+			let (before, after) = uri.split_at(start);
+			(before.ends_with(b"ipfs://") || before.ends_with(b"/ipfs/"))
+				&& after.starts_with(&text)
+				&& matches!(after.get(text.len()), None | Some(b'/'))
+		})
+	}
+
+	/// Whether `content` fetched from `uri` is the content of the cid. `None` when the content
+	/// alone cannot tell: the uri has a path inside the cid or the content is wrapped in a node.
+	pub fn verifies(&self, uri: &[u8], content: &[u8]) -> Option<bool> {
+		match self {
+			Cid::V1 { codec: RAW, hash } if uri.ends_with(&self.to_text()) => {
+				Some(hash.matches(content))
+			},
+			_ => None,
+		}
+	}
+}
+
+/// Unsigned LEB128, the varint of multiformats.
+fn put_varint(mut n: u64, out: &mut Vec<u8>) {
+	loop {
+		let byte = (n & 0x7f) as u8;
+		n >>= 7;
+		if n == 0 {
+			out.push(byte);
+			return;
+		}
+		out.push(byte | 0x80);
+	}
+}
+
+fn base58(bytes: &[u8]) -> Vec<u8> {
+	// digits of the number in base 58, least significant first
+	let mut digits: Vec<u8> = Vec::new();
+	for byte in bytes {
+		let mut carry = *byte as u32;
+		for digit in digits.iter_mut() {
+			carry += (*digit as u32) << 8;
+			*digit = (carry % 58) as u8;
+			carry /= 58;
+		}
+		while carry > 0 {
+			digits.push((carry % 58) as u8);
+			carry /= 58;
+		}
+	}
+	let zeros = bytes.iter().take_while(|byte| **byte == 0).count();
+	iter::repeat(b'1')
+		.take(zeros)
+		.chain(digits.iter().rev().map(|digit| BASE58[*digit as usize]))
+		.collect()
+}
+
+fn base32(bytes: &[u8]) -> Vec<u8> {
+	let mut out = Vec::new();
+	let (mut buffer, mut bits) = (0u32, 0u32);
+	for byte in bytes {
+		buffer = ((buffer << 8) | *byte as u32) & 0xfff;
+		bits += 8;
+		while bits >= 5 {
+			bits -= 5;
+			out.push(BASE32[((buffer >> bits) & 31) as usize]);
+		}
+	}
+	if bits > 0 {
+		out.push(BASE32[((buffer << (5 - bits)) & 31) as usize]);
+	}
+	out
+}
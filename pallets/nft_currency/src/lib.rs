@@ -1,7 +1,15 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 #[cfg(feature = "runtime-benchmarks")]
 mod benchmarking;
+pub mod cid;
+pub mod metadata;
+#[cfg(test)]
+mod mock;
 pub mod nft;
+mod nonfungibles;
+#[cfg(test)]
+mod tests;
+pub mod weights;
 
 use codec::Encode;
 use frame_support::pallet_prelude::{StorageMap, StorageValue};
@@ -10,30 +18,46 @@ use frame_support::{
 	ensure, log,
 	traits::{Get, Randomness},
 };
-use frame_system::{ensure_signed, RawOrigin};
+use frame_system::{ensure_signed, offchain::CreateSignedTransaction, RawOrigin};
 pub use sp_std::{convert::Into, vec::Vec};
 
+pub use cid::Cid;
+pub use metadata::{MetadataCheck, MetadataStatus, Verdict};
 pub use nft::NonFungibleToken;
 /// Edit this file to define custom logic or remove it if it is not needed.
 /// Learn more about FRAME and the core library of Substrate FRAME pallets:
 /// <https://docs.substrate.io/v3/runtime/frame>
 pub use pallet::*;
+pub use weights::WeightInfo;
 
 #[frame_support::pallet]
 pub mod pallet {
 	use frame_support::pallet_prelude::*;
 	use frame_support::traits::Randomness;
-	use frame_system::pallet_prelude::*;
+	use frame_system::{offchain::AppCrypto, pallet_prelude::*};
 
 	pub use super::*;
 
 	/// Configure the pallet by specifying the parameters and types on which it depends.
 	#[pallet::config]
-	pub trait Config: frame_system::Config {
+	pub trait Config: frame_system::Config + CreateSignedTransaction<Call<Self>> {
 		/// Because this pallet emits events, it depends on the runtime's definition of an event.
 		type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
-		// type Administrator : EnsureOrigin<Self::Origin>;
+		/// Origin managing the minters and locking the collection.
+		type AdminOrigin: EnsureOrigin<Self::RuntimeOrigin>;
 		type Randomness: Randomness<Self::Hash, Self::BlockNumber>;
+		/// Keys the off-chain worker signs metadata reports with.
+		type AuthorityId: AppCrypto<Self::Public, Self::Signature>;
+		/// Origin of the metadata reports, the accounts of the `AuthorityId` keys.
+		type VerifierOrigin: EnsureOrigin<Self::RuntimeOrigin, Success = Self::AccountId>;
+		/// Blocks after which valid or unreachable metadata is fetched again.
+		#[pallet::constant]
+		type RecheckInterval: Get<Self::BlockNumber>;
+		/// Most tokens the off-chain worker checks in one block.
+		#[pallet::constant]
+		type MaxMetadataChecks: Get<u32>;
+		/// Weight information for extrinsics in this pallet.
+		type WeightInfo: WeightInfo;
 	}
 
 	#[pallet::pallet]
@@ -47,6 +71,23 @@ pub mod pallet {
 	pub(super) type TokenUri<T: Config> =
 		StorageMap<_, Blake2_128Concat, Vec<u8>, Vec<u8>, OptionQuery>;
 
+	#[pallet::storage]
+	#[pallet::getter(fn token_cid)]
+	// content identifier of the metadata behind an ipfs uri
+	pub(super) type TokenCid<T: Config> =
+		StorageMap<_, Blake2_128Concat, Vec<u8>, Cid, OptionQuery>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn collection_locked)]
+	// once locked, the uri of a token cannot change after it is first set
+	pub(super) type CollectionLocked<T> = StorageValue<_, bool, ValueQuery>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn is_minter)]
+	// accounts allowed to mint, anyone may mint while there is none
+	pub(super) type Minters<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::AccountId, (), OptionQuery>;
+
 	#[pallet::storage]
 	#[pallet::getter(fn total_tokens)]
 	// total count of the token
@@ -77,6 +118,12 @@ pub mod pallet {
 	pub(super) type Approval<T: Config> =
 		StorageMap<_, Blake2_128Concat, (T::AccountId, T::AccountId), bool, OptionQuery>;
 
+	#[pallet::storage]
+	#[pallet::getter(fn metadata_check)]
+	// token Id => last check of the metadata behind its uri
+	pub(super) type MetadataChecks<T: Config> =
+		StorageMap<_, Blake2_128Concat, Vec<u8>, MetadataCheck<T::BlockNumber>, OptionQuery>;
+
 	#[pallet::storage]
 	#[pallet::getter(fn token_approval)]
 	pub(super) type TokenApproval<T: Config> =
@@ -95,6 +142,16 @@ pub mod pallet {
 		SetURI(Vec<u8>, Vec<u8>),
 		Approve(T::AccountId, T::AccountId, Vec<u8>),
 		ApproveForAll(T::AccountId, T::AccountId),
+		/// A verifier reported on the metadata of a token. [token, status]
+		MetadataChecked(Vec<u8>, MetadataStatus),
+		/// The uri of the token is content addressed. [token, cid]
+		SetCid(Vec<u8>, Cid),
+		/// The uris of the tokens are frozen.
+		CollectionLocked,
+		/// [minter]
+		MinterAdded(T::AccountId),
+		/// [minter]
+		MinterRemoved(T::AccountId),
 	}
 
 	// Errors inform users that something went wrong.
@@ -108,6 +165,23 @@ pub mod pallet {
 		NotOwnerNorApproved,
 		NotCustodian,
 		InRent,
+		AlreadyExist,
+		/// The uri of the token changed since its metadata was fetched.
+		OutdatedReport,
+		/// The cid is malformed or not the one named by the uri.
+		InvalidCid,
+		/// The collection is locked and the token already has a uri.
+		UriFrozen,
+		AlreadyLocked,
+		/// Minting is restricted to the minters and the caller is not one of them.
+		NotMinter,
+	}
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+		fn offchain_worker(now: T::BlockNumber) {
+			Self::check_metadata(now);
+		}
 	}
 
 	// Dispatchable functions allows users to interact with the pallet and invoke state changes.
@@ -115,38 +189,42 @@ pub mod pallet {
 	// Dispatchable functions must be annotated with a weight and must return a DispatchResult.
 	#[pallet::call]
 	impl<T: Config> Pallet<T> {
-		#[pallet::weight(33_963_000 + T::DbWeight::get().reads_writes(4, 3).ref_time())]
+		#[pallet::weight(T::WeightInfo::mint_token())]
 		pub fn mint_to(
-			_origin: OriginFor<T>,
+			origin: OriginFor<T>,
 			to: T::AccountId,
 			token_uri: Vec<u8>,
 		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			ensure!(Self::may_mint(&who), Error::<T>::NotMinter);
 			let token_id = <Self as NonFungibleToken<_>>::mint(to.clone())?;
 			Self::deposit_event(Event::Mint(to.clone(), token_id.clone()));
-			Self::set_token_uri(RawOrigin::Signed(to).into(), token_id, token_uri);
-			Ok(())
+			Self::set_token_uri(RawOrigin::Signed(to).into(), token_id, token_uri, None)
 		}
 
-		#[pallet::weight(35_678_000 + T::DbWeight::get().reads_writes(3, 3).ref_time())]
+		#[pallet::weight(T::WeightInfo::transfer_ownership())]
 		pub fn transfer_ownership(
 			origin: OriginFor<T>,
 			to: T::AccountId,
 			token_id: Vec<u8>,
 		) -> DispatchResult {
 			let who = ensure_signed(origin)?;
-			ensure!(who == Self::owner_of(token_id.clone()).unwrap(), Error::<T>::NotOwner);
-			ensure!(who == Self::custodian_of_token(token_id.clone()), Error::<T>::InRent);
+			let owner = Self::owner_of(token_id.clone()).ok_or(Error::<T>::NoneExist)?;
+			ensure!(who == owner, Error::<T>::NotOwner);
+			ensure!(
+				Some(who.clone()) == Self::custodian_of_token(token_id.clone()),
+				Error::<T>::InRent
+			);
 			<Self as NonFungibleToken<_>>::transfer_ownership(
 				who.clone(),
 				to.clone(),
 				token_id.clone(),
-			)
-			.expect("Cannot transfer token");
+			)?;
 			Self::deposit_event(Event::Transfer(who, to, token_id));
 			Ok(())
 		}
 
-		#[pallet::weight(54_275_000 + T::DbWeight::get().reads_writes(4, 3).ref_time())]
+		#[pallet::weight(T::WeightInfo::safe_transfer_ownership())]
 		pub fn safe_transfer_ownership(
 			origin: OriginFor<T>,
 			from: T::AccountId,
@@ -154,11 +232,11 @@ pub mod pallet {
 			token_id: Vec<u8>,
 		) -> DispatchResult {
 			let who = ensure_signed(origin)?;
+			let owner = Self::owner_of(token_id.clone()).ok_or(Error::<T>::NoneExist)?;
 			let account = (from.clone(), who.clone());
 			ensure!(
-				who == Self::owner_of(token_id.clone()).unwrap()
-					&& who == Self::custodian_of(token_id.clone()).unwrap()
-					|| Self::is_approve_for_all(account).unwrap(),
+				who == owner && Some(who.clone()) == Self::custodian_of_token(token_id.clone())
+					|| Self::is_approve_for_all(account).unwrap_or(false),
 				Error::<T>::NotOwnerNorApproved
 			);
 
@@ -171,16 +249,16 @@ pub mod pallet {
 			Ok(())
 		}
 
-		#[pallet::weight(38_030_000 + T::DbWeight::get().reads_writes(2, 1).ref_time())]
+		#[pallet::weight(T::WeightInfo::approve())]
 		pub fn approve(
 			origin: OriginFor<T>,
 			to: T::AccountId,
 			token_id: Vec<u8>,
 		) -> DispatchResult {
 			let who = ensure_signed(origin)?;
+			let owner = Self::owner_of(token_id.clone()).ok_or(Error::<T>::NoneExist)?;
 			ensure!(
-				who == Self::owner_of(token_id.clone()).unwrap()
-					|| who == Self::custodian_of(token_id.clone()).unwrap(),
+				who == owner || Some(who.clone()) == Self::custodian_of(token_id.clone()),
 				Error::<T>::NotOwner
 			);
 			<Self as NonFungibleToken<_>>::approve(who.clone(), to.clone(), token_id.clone())?;
@@ -188,7 +266,7 @@ pub mod pallet {
 			Ok(())
 		}
 
-		#[pallet::weight(26_615_000 + T::DbWeight::get().reads_writes(1, 1).ref_time())]
+		#[pallet::weight(T::WeightInfo::approve_for_all())]
 		pub fn approve_for_all(origin: OriginFor<T>, account: T::AccountId) -> DispatchResult {
 			let who = ensure_signed(origin)?;
 			<Self as NonFungibleToken<_>>::set_approve_for_all(who.clone(), account.clone())?;
@@ -196,16 +274,85 @@ pub mod pallet {
 			Ok(())
 		}
 
-		#[pallet::weight(17_653_000 + T::DbWeight::get().reads_writes(2, 1).ref_time())]
+		/// Set the uri of a token, with the `cid` of its content when the uri is an ipfs one.
+		#[pallet::weight(T::WeightInfo::set_token_uri())]
 		pub fn set_token_uri(
 			origin: OriginFor<T>,
 			token_id: Vec<u8>,
 			token_uri: Vec<u8>,
+			cid: Option<Cid>,
 		) -> DispatchResult {
 			let who = ensure_signed(origin)?;
-			ensure!(who == Self::owner_of(token_id.clone()).unwrap(), Error::<T>::NotOwner);
+			let owner = Self::owner_of(token_id.clone()).ok_or(Error::<T>::NoneExist)?;
+			ensure!(who == owner, Error::<T>::NotOwner);
+			if let Some(cid) = &cid {
+				ensure!(cid.is_valid() && cid.is_named_by(&token_uri), Error::<T>::InvalidCid);
+			}
 			<Self as NonFungibleToken<_>>::set_token_uri(token_id.clone(), token_uri.clone())?;
-			Self::deposit_event(Event::SetUri(token_id, token_uri));
+			Self::deposit_event(Event::SetUri(token_id.clone(), token_uri));
+			if let Some(cid) = cid {
+				TokenCid::<T>::insert(&token_id, cid.clone());
+				Self::deposit_event(Event::SetCid(token_id, cid));
+			}
+			Ok(())
+		}
+
+		/// Freeze the uris of the tokens, a token minted later keeps the first uri it is given.
+		#[pallet::weight(T::WeightInfo::lock_collection())]
+		pub fn lock_collection(origin: OriginFor<T>) -> DispatchResult {
+			T::AdminOrigin::ensure_origin(origin)?;
+			ensure!(!Self::collection_locked(), Error::<T>::AlreadyLocked);
+			CollectionLocked::<T>::put(true);
+			Self::deposit_event(Event::CollectionLocked);
+			Ok(())
+		}
+
+		/// Allow `minter` to mint. Once there is a minter, only minters can mint.
+		#[pallet::weight(T::WeightInfo::add_minter())]
+		pub fn add_minter(origin: OriginFor<T>, minter: T::AccountId) -> DispatchResult {
+			T::AdminOrigin::ensure_origin(origin)?;
+			Minters::<T>::insert(&minter, ());
+			Self::deposit_event(Event::MinterAdded(minter));
+			Ok(())
+		}
+
+		/// Take the right to mint from `minter`, minting is open again after the last one.
+		#[pallet::weight(T::WeightInfo::remove_minter())]
+		pub fn remove_minter(origin: OriginFor<T>, minter: T::AccountId) -> DispatchResult {
+			T::AdminOrigin::ensure_origin(origin)?;
+			ensure!(Minters::<T>::contains_key(&minter), Error::<T>::NotMinter);
+			Minters::<T>::remove(&minter);
+			Self::deposit_event(Event::MinterRemoved(minter));
+			Ok(())
+		}
+
+		/// Record what the off-chain worker found behind the uri with hash `uri_hash`.
+		#[pallet::weight(T::WeightInfo::report_metadata())]
+		pub fn report_metadata(
+			origin: OriginFor<T>,
+			token_id: Vec<u8>,
+			uri_hash: [u8; 32],
+			verdict: Verdict,
+		) -> DispatchResult {
+			T::VerifierOrigin::ensure_origin(origin)?;
+			let uri = Self::token_uri(token_id.clone()).ok_or(Error::<T>::NoneExist)?;
+			ensure!(sp_io::hashing::blake2_256(&uri) == uri_hash, Error::<T>::OutdatedReport);
+			let pinned = MetadataChecks::<T>::get(&token_id).and_then(|check| check.content_hash);
+			let (status, content_hash) = match verdict {
+				Verdict::Valid(hash) => match pinned {
+					Some(pinned) if pinned != hash => (MetadataStatus::Mutated, Some(pinned)),
+					_ => (MetadataStatus::Valid, Some(hash)),
+				},
+				Verdict::InvalidSchema => (MetadataStatus::InvalidSchema, pinned),
+				Verdict::Unreachable => (MetadataStatus::Unreachable, pinned),
+				Verdict::Mismatch => (MetadataStatus::Mutated, pinned),
+			};
+			let checked_at = frame_system::Pallet::<T>::block_number();
+			MetadataChecks::<T>::insert(
+				&token_id,
+				MetadataCheck { status, content_hash, checked_at },
+			);
+			Self::deposit_event(Event::MetadataChecked(token_id, status));
 			Ok(())
 		}
 	}
@@ -219,25 +366,14 @@ impl<T: Config> Pallet<T> {
 		let (rand, _) = T::Randomness::random(&n);
 		rand.encode()
 	}
-}
 
-impl<T: Config> NonFungibleToken<T::AccountId> for Pallet<T> {
-	fn token_uri(token_id: Vec<u8>) -> Vec<u8> {
-		TokenUri::<T>::get(token_id).unwrap()
+	/// Whether `who` may mint: anyone while no minter is set, the minters after that.
+	fn may_mint(who: &T::AccountId) -> bool {
+		Minters::<T>::iter_keys().next().is_none() || Minters::<T>::contains_key(who)
 	}
 
-	fn custodian_of_token(token_id: Vec<u8>) -> T::AccountId {
-		let account = CustodianOf::<T>::get(token_id).unwrap();
-		account
-	}
-
-	fn owner_of_token(token_id: Vec<u8>) -> T::AccountId {
-		let account = OwnerOf::<T>::get(token_id).unwrap();
-		account
-	}
-
-	fn mint(owner: T::AccountId) -> Result<Vec<u8>, DispatchError> {
-		let token_id = Self::gen_token_id();
+	/// Record `owner` as owner and custodian of the new token `token_id`.
+	fn insert_token(token_id: Vec<u8>, owner: T::AccountId) {
 		TotalTokens::<T>::mutate(|value| *value += 1);
 		OwnerOf::<T>::mutate(token_id.clone(), |account| {
 			*account = Some(owner.clone());
@@ -248,9 +384,29 @@ impl<T: Config> NonFungibleToken<T::AccountId> for Pallet<T> {
 		TokenApproval::<T>::mutate(token_id.clone(), |approval| {
 			approval.push(owner.clone());
 		});
-		CustodianOf::<T>::mutate(token_id.clone(), |account| {
-			*account = Some(owner.clone());
+		CustodianOf::<T>::mutate(token_id, |account| {
+			*account = Some(owner);
 		});
+	}
+}
+
+impl<T: Config> NonFungibleToken<T::AccountId> for Pallet<T> {
+	fn token_uri(token_id: Vec<u8>) -> Option<Vec<u8>> {
+		TokenUri::<T>::get(token_id)
+	}
+
+	fn custodian_of_token(token_id: Vec<u8>) -> Option<T::AccountId> {
+		// the custodian is cleared when a rented token goes back to its owner
+		CustodianOf::<T>::get(token_id.clone()).or_else(|| OwnerOf::<T>::get(token_id))
+	}
+
+	fn owner_of_token(token_id: Vec<u8>) -> Option<T::AccountId> {
+		OwnerOf::<T>::get(token_id)
+	}
+
+	fn mint(owner: T::AccountId) -> Result<Vec<u8>, DispatchError> {
+		let token_id = Self::gen_token_id();
+		Self::insert_token(token_id.clone(), owner);
 		Ok(token_id)
 	}
 
@@ -259,6 +415,12 @@ impl<T: Config> NonFungibleToken<T::AccountId> for Pallet<T> {
 		to: T::AccountId,
 		token_id: Vec<u8>,
 	) -> DispatchResult {
+		let owner = Self::owner_of(token_id.clone()).ok_or(Error::<T>::NoneExist)?;
+		ensure!(owner == from, Error::<T>::NotOwner);
+		// a token that is not rented out stays with its owner
+		if Self::custodian_of(token_id.clone()).as_ref() == Some(&from) {
+			CustodianOf::<T>::remove(token_id.clone());
+		}
 		OwnerOf::<T>::mutate(token_id.clone(), |owner| *owner = Some(to.clone()));
 		ListOwned::<T>::mutate(to, |list_token| {
 			list_token.push(token_id.clone());
@@ -268,9 +430,8 @@ impl<T: Config> NonFungibleToken<T::AccountId> for Pallet<T> {
 				list_token.swap_remove(ind);
 				return Ok(());
 			}
-			Err(())
-		})
-		.expect("Error in ListOwned");
+			Err(Error::<T>::NoneExist)
+		})?;
 		Ok(())
 	}
 
@@ -281,14 +442,13 @@ impl<T: Config> NonFungibleToken<T::AccountId> for Pallet<T> {
 		to: T::AccountId,
 		token_id: Vec<u8>,
 	) -> DispatchResult {
+		let owner = Self::owner_of(token_id.clone()).ok_or(Error::<T>::NoneExist)?;
+		let custodian = Self::custodian_of(token_id.clone());
 		ensure!(
-			Self::owner_of(token_id.clone()).unwrap() == from
-				&& Self::custodian_of(token_id.clone()).is_none()
-				|| !Self::custodian_of(token_id.clone()).is_none()
-					&& Self::custodian_of(token_id.clone()).unwrap() == from,
+			owner == from && custodian.is_none() || custodian.as_ref() == Some(&from),
 			Error::<T>::NotCustodian
 		);
-		if to == Self::owner_of_token(token_id.clone()) {
+		if to == owner {
 			CustodianOf::<T>::remove(token_id.clone());
 		} else {
 			CustodianOf::<T>::mutate(token_id.clone(), |custodian| *custodian = Some(to));
@@ -297,11 +457,11 @@ impl<T: Config> NonFungibleToken<T::AccountId> for Pallet<T> {
 	}
 
 	fn is_approve_for_all(account_approve: (T::AccountId, T::AccountId)) -> bool {
-		Approval::<T>::get(account_approve).unwrap()
+		Approval::<T>::get(account_approve).unwrap_or(false)
 	}
 
 	fn approve(from: T::AccountId, to: T::AccountId, token_id: Vec<u8>) -> DispatchResult {
-		let owner = OwnerOf::<T>::get(token_id.clone()).unwrap();
+		let owner = OwnerOf::<T>::get(token_id.clone()).ok_or(Error::<T>::NoneExist)?;
 		ensure!(from == owner, "Not Owner nor approved");
 		TokenApproval::<T>::mutate(token_id.clone(), |list_account| {
 			list_account.push(to);
@@ -318,7 +478,21 @@ impl<T: Config> NonFungibleToken<T::AccountId> for Pallet<T> {
 	}
 
 	fn set_token_uri(token_id: Vec<u8>, token_uri: Vec<u8>) -> DispatchResult {
-		TokenUri::<T>::mutate(token_id, |uri| *uri = Some(token_uri));
+		ensure!(OwnerOf::<T>::contains_key(token_id.clone()), Error::<T>::NoneExist);
+		ensure!(
+			!CollectionLocked::<T>::get() || !TokenUri::<T>::contains_key(&token_id),
+			Error::<T>::UriFrozen
+		);
+		TokenUri::<T>::mutate(token_id.clone(), |uri| *uri = Some(token_uri));
+		// the cid of the previous uri does not name the new one
+		TokenCid::<T>::remove(&token_id);
+		// metadata behind a new uri is checked from scratch
+		let check = MetadataCheck {
+			status: MetadataStatus::Pending,
+			content_hash: None,
+			checked_at: frame_system::Pallet::<T>::block_number(),
+		};
+		MetadataChecks::<T>::insert(token_id, check);
 		Ok(())
 	}
 }
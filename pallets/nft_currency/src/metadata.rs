@@ -0,0 +1,183 @@
+//! Verification of the metadata behind token uris by an off-chain worker.
+//!
+//! Setting a uri marks the metadata of the token as `Pending`. The worker fetches pending
+//! metadata over HTTP, valid or unreachable metadata again every `RecheckInterval` blocks, and
+//! reports what it found with a signed `report_metadata`. Metadata is valid when it is a JSON
+//! object with the `name`, `description` and `image` strings of the ERC-721 metadata schema. The
+//! hash of the first valid content is kept, later content with another hash is `Mutated`. So is
+//! content that does not hash to the `raw` cid pinned for the uri.
+
+use crate::{Call, Cid, Config, MetadataChecks, Pallet, TokenCid, TokenUri};
+use frame_support::{log, pallet_prelude::*};
+use frame_system::offchain::{SendSignedTransaction, Signer};
+use lite_json::{json::JsonValue, json_parser::parse_json};
+use sp_runtime::{
+	offchain::{http, storage::StorageValueRef, Duration},
+	traits::Saturating,
+	KeyTypeId,
+};
+use sp_std::{str, vec::Vec};
+
+/// Key type of the accounts signing metadata reports.
+pub const KEY_TYPE: KeyTypeId = KeyTypeId(*b"nftm");
+
+/// How long the worker waits for a uri to answer.
+const FETCH_TIMEOUT_MS: u64 = 3_000;
+/// Blocks before a report that did not make it on chain is sent again.
+const RESUBMIT_AFTER: u32 = 5;
+
+/// Keys of the metadata verifiers, sr25519 accounts of the `KEY_TYPE` keystore.
+pub mod crypto {
+	use super::KEY_TYPE;
+	use sp_runtime::{
+		app_crypto::{app_crypto, sr25519},
+		MultiSignature, MultiSigner,
+	};
+	app_crypto!(sr25519, KEY_TYPE);
+
+	pub struct MetadataAuthId;
+
+	impl frame_system::offchain::AppCrypto<MultiSigner, MultiSignature> for MetadataAuthId {
+		type RuntimeAppPublic = Public;
+		type GenericSignature = sp_core::sr25519::Signature;
+		type GenericPublic = sp_core::sr25519::Public;
+	}
+}
+
+/// What is known about the metadata of a token.
+#[derive(Clone, Copy, Encode, Decode, PartialEq, Eq, TypeInfo, Debug)]
+pub enum MetadataStatus {
+	/// The uri changed and was not checked yet.
+	Pending,
+	/// The content follows the schema and still has the hash it was first seen with.
+	Valid,
+	/// The uri is not an HTTP url, did not answer in time or answered with an error.
+	Unreachable,
+	/// The content is not a JSON object with the fields of the schema.
+	InvalidSchema,
+	/// The content changed since it was found valid, or is not the content of the cid.
+	Mutated,
+}
+
+/// Last check of the metadata of a token.
+#[derive(Clone, Encode, Decode, PartialEq, Eq, TypeInfo, Debug)]
+pub struct MetadataCheck<BlockNumber> {
+	pub status: MetadataStatus,
+	/// blake2_256 of the first valid content, `None` until the metadata is found valid.
+	pub content_hash: Option<[u8; 32]>,
+	pub checked_at: BlockNumber,
+}
+
+/// What the worker found behind a uri.
+#[derive(Clone, Copy, Encode, Decode, PartialEq, Eq, TypeInfo, Debug)]
+pub enum Verdict {
+	/// The content follows the schema, with its blake2_256 hash.
+	Valid([u8; 32]),
+	InvalidSchema,
+	Unreachable,
+	/// The content is not the one named by the cid of the token.
+	Mismatch,
+}
+
+/// Whether `body` is a JSON object with the `name`, `description` and `image` strings.
+pub fn follows_schema(body: &[u8]) -> bool {
+	let body = match str::from_utf8(body) {
+		Ok(body) => body,
+		Err(_) => return false,
+	};
+	let fields = match parse_json(body) {
+		Ok(JsonValue::Object(fields)) => fields,
+		_ => return false,
+	};
+	[&b"name"[..], b"description", b"image"].iter().all(|key| {
+		fields.iter().any(|(k, v)| {
+			k.iter().copied().eq(key.iter().map(|b| *b as char))
+				&& matches!(v, JsonValue::String(_))
+		})
+	})
+}
+
+/// Fetch `uri` with a GET request, the body of a `200` answer.
+fn fetch(uri: &[u8]) -> Result<Vec<u8>, http::Error> {
+	let uri = str::from_utf8(uri).map_err(|_| http::Error::Unknown)?;
+	if !uri.starts_with("http://") && !uri.starts_with("https://") {
+		return Err(http::Error::Unknown);
+	}
+	let deadline = sp_io::offchain::timestamp().add(Duration::from_millis(FETCH_TIMEOUT_MS));
+	let pending = http::Request::get(uri)
+		.deadline(deadline)
+		.send()
+		.map_err(|_| http::Error::IoError)?;
+	let response = pending.try_wait(deadline).map_err(|_| http::Error::DeadlineReached)??;
+	if response.code != 200 {
+		return Err(http::Error::Unknown);
+	}
+	Ok(response.body().collect())
+}
+
+/// Check the metadata behind `uri`, against the `cid` pinned for it if any.
+pub fn verify(uri: &[u8], cid: Option<&Cid>) -> Verdict {
+	match fetch(uri) {
+		Ok(body) if cid.and_then(|cid| cid.verifies(uri, &body)) == Some(false) => {
+			Verdict::Mismatch
+		},
+		Ok(body) if follows_schema(&body) => Verdict::Valid(sp_io::hashing::blake2_256(&body)),
+		Ok(_) => Verdict::InvalidSchema,
+		Err(_) => Verdict::Unreachable,
+	}
+}
+
+impl<T: Config> Pallet<T> {
+	/// Check the metadata that is pending or due for a recheck, up to `MaxMetadataChecks` tokens.
+	pub(crate) fn check_metadata(now: T::BlockNumber) {
+		let signer = Signer::<T, T::AuthorityId>::any_account();
+		if !signer.can_sign() {
+			return;
+		}
+		// a token whose report may still be on its way is skipped before counting the checks
+		let due = MetadataChecks::<T>::iter()
+			.filter(|(_, check)| match check.status {
+				MetadataStatus::Pending => true,
+				MetadataStatus::Valid | MetadataStatus::Unreachable => {
+					now >= check.checked_at.saturating_add(T::RecheckInterval::get())
+				},
+				_ => false,
+			})
+			.filter_map(|(token_id, _)| {
+				let uri = TokenUri::<T>::get(&token_id)?;
+				let uri_hash = sp_io::hashing::blake2_256(&uri);
+				(!Self::report_sent(&token_id, uri_hash, now)).then_some((token_id, uri, uri_hash))
+			})
+			.take(T::MaxMetadataChecks::get() as usize);
+		for (token_id, uri, uri_hash) in due {
+			let verdict = verify(&uri, TokenCid::<T>::get(&token_id).as_ref());
+			let result = signer.send_signed_transaction(|_| Call::report_metadata {
+				token_id: token_id.clone(),
+				uri_hash,
+				verdict,
+			});
+			match result {
+				Some((_, Ok(()))) => {
+					StorageValueRef::persistent(&Self::report_key(&token_id)).set(&(uri_hash, now))
+				},
+				_ => log::error!("failed to report the metadata of token {:?}", token_id),
+			}
+		}
+	}
+
+	/// Whether the report on `uri_hash` sent for `token_id` may still be on its way.
+	fn report_sent(token_id: &[u8], uri_hash: [u8; 32], now: T::BlockNumber) -> bool {
+		let key = Self::report_key(token_id);
+		match StorageValueRef::persistent(&key).get::<([u8; 32], T::BlockNumber)>() {
+			Ok(Some((hash, at))) => {
+				hash == uri_hash && now < at.saturating_add(RESUBMIT_AFTER.into())
+			},
+			_ => false,
+		}
+	}
+
+	/// Offchain storage key of the last report sent for `token_id`.
+	fn report_key(token_id: &[u8]) -> Vec<u8> {
+		(b"nft_currency::metadata", token_id).encode()
+	}
+}
@@ -3,9 +3,11 @@ use sp_std::vec::Vec;
 use sp_std::*;
 
 pub trait NonFungibleToken<AccountId> {
-	fn token_uri(token_id: Vec<u8>) -> Vec<u8>;
-	fn custodian_of_token(token_id: Vec<u8>) -> AccountId;
-	fn owner_of_token(token_id: Vec<u8>) -> AccountId;
+	/// Uri of the token, `None` if the token does not exist or has no uri.
+	fn token_uri(token_id: Vec<u8>) -> Option<Vec<u8>>;
+	/// Account holding the token, which is the owner unless the token is rented out.
+	fn custodian_of_token(token_id: Vec<u8>) -> Option<AccountId>;
+	fn owner_of_token(token_id: Vec<u8>) -> Option<AccountId>;
 
 	fn mint(owner: AccountId) -> Result<Vec<u8>, DispatchError>;
 	fn transfer_ownership(from: AccountId, to: AccountId, token_id: Vec<u8>) -> DispatchResult;
@@ -0,0 +1,69 @@
+//! The tokens of the pallet behind the `nonfungibles` traits of FRAME.
+//!
+//! All tokens belong to a single collection, `()`, and are identified by their token id. The
+//! attribute with the empty key is the uri of a token. A token cannot be transferred while it is
+//! rented out, that is while its custodian is not its owner.
+
+use crate::{Config, CustodianOf, Error, NonFungibleToken, OwnerOf, Pallet, TokenUri};
+use frame_support::{
+	dispatch::DispatchResult,
+	ensure,
+	traits::tokens::nonfungibles::{Create, Inspect, Mutate, Transfer},
+};
+use sp_std::vec::Vec;
+
+impl<T: Config> Inspect<T::AccountId> for Pallet<T> {
+	type ItemId = Vec<u8>;
+	type CollectionId = ();
+
+	fn owner(_collection: &(), item: &Vec<u8>) -> Option<T::AccountId> {
+		OwnerOf::<T>::get(item)
+	}
+
+	fn attribute(_collection: &(), item: &Vec<u8>, key: &[u8]) -> Option<Vec<u8>> {
+		if key.is_empty() {
+			TokenUri::<T>::get(item)
+		} else {
+			None
+		}
+	}
+
+	fn can_transfer(_collection: &(), item: &Vec<u8>) -> bool {
+		match OwnerOf::<T>::get(item) {
+			Some(owner) => CustodianOf::<T>::get(item).map_or(true, |custodian| custodian == owner),
+			None => false,
+		}
+	}
+}
+
+impl<T: Config> Create<T::AccountId> for Pallet<T> {
+	/// The only collection always exists.
+	fn create_collection(
+		_collection: &(),
+		_who: &T::AccountId,
+		_admin: &T::AccountId,
+	) -> DispatchResult {
+		Err(Error::<T>::AlreadyExist.into())
+	}
+}
+
+impl<T: Config> Mutate<T::AccountId> for Pallet<T> {
+	fn mint_into(_collection: &(), item: &Vec<u8>, who: &T::AccountId) -> DispatchResult {
+		ensure!(!OwnerOf::<T>::contains_key(item), Error::<T>::AlreadyExist);
+		Self::insert_token(item.clone(), who.clone());
+		Ok(())
+	}
+
+	fn set_attribute(_collection: &(), item: &Vec<u8>, key: &[u8], value: &[u8]) -> DispatchResult {
+		ensure!(key.is_empty(), Error::<T>::Invalid);
+		<Self as NonFungibleToken<_>>::set_token_uri(item.clone(), value.to_vec())
+	}
+}
+
+impl<T: Config> Transfer<T::AccountId> for Pallet<T> {
+	fn transfer(collection: &(), item: &Vec<u8>, destination: &T::AccountId) -> DispatchResult {
+		let owner = OwnerOf::<T>::get(item).ok_or(Error::<T>::NoneExist)?;
+		ensure!(Self::can_transfer(collection, item), Error::<T>::InRent);
+		<Self as NonFungibleToken<_>>::transfer_ownership(owner, destination.clone(), item.clone())
+	}
+}
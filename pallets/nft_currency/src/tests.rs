@@ -0,0 +1,739 @@
+use crate::{
+	cid::{self, Cid, Multihash},
+	metadata,
+	mock::*,
+	Error, Event, MetadataCheck, MetadataStatus, NonFungibleToken, TokenApproval, Verdict,
+};
+use codec::{Decode, Encode};
+use frame_support::{
+	assert_noop, assert_ok,
+	traits::{
+		tokens::nonfungibles::{Create, Inspect, Mutate, Transfer},
+		OffchainWorker,
+	},
+};
+use parking_lot::RwLock;
+use sp_core::offchain::{
+	testing::{self, OffchainState, PoolState},
+	OffchainDbExt, OffchainWorkerExt, TransactionPoolExt,
+};
+use sp_runtime::testing::UintAuthorityId;
+use sp_runtime::traits::{BlakeTwo256, Hash};
+use std::sync::Arc;
+
+const UNKNOWN: [u8; 32] = [7u8; 32];
+
+fn mint(owner: u64) -> Vec<u8> {
+	assert_ok!(NftCurrency::mint_to(RuntimeOrigin::signed(owner), owner, b"uri".to_vec()));
+	NftCurrency::list_owned(owner).last().cloned().unwrap()
+}
+
+#[test]
+fn mint_records_owner_and_uri() {
+	new_test_ext().execute_with(|| {
+		let token = mint(1);
+		// ids come from the mock randomness seeded with the number of minted tokens
+		assert_eq!(token, BlakeTwo256::hash(&0u32.encode()).encode());
+		assert_eq!(NftCurrency::total_tokens(), 1);
+		assert_eq!(NftCurrency::owner_of(token.clone()), Some(1));
+		assert_eq!(NftCurrency::list_owned(1), vec![token.clone()]);
+		assert_eq!(NftCurrency::token_uri(token.clone()), Some(b"uri".to_vec()));
+		System::assert_has_event(Event::Mint(1, token.clone()).into());
+
+		let second = mint(1);
+		assert_ne!(second, token);
+		assert_eq!(NftCurrency::list_owned(1).len(), 2);
+	});
+}
+
+#[test]
+fn owner_transfers_token() {
+	new_test_ext().execute_with(|| {
+		let token = mint(1);
+		assert_noop!(
+			NftCurrency::transfer_ownership(RuntimeOrigin::signed(2), 3, token.clone()),
+			Error::<Test>::NotOwner
+		);
+		assert_ok!(NftCurrency::transfer_ownership(RuntimeOrigin::signed(1), 2, token.clone()));
+		assert_eq!(NftCurrency::owner_of(token.clone()), Some(2));
+		assert!(NftCurrency::list_owned(1).is_empty());
+		assert_eq!(NftCurrency::list_owned(2), vec![token.clone()]);
+		System::assert_last_event(Event::Transfer(1, 2, token).into());
+	});
+}
+
+#[test]
+fn operator_safe_transfers_token() {
+	new_test_ext().execute_with(|| {
+		let token = mint(1);
+		assert_noop!(
+			NftCurrency::safe_transfer_ownership(RuntimeOrigin::signed(2), 1, 3, token.clone()),
+			Error::<Test>::NotOwnerNorApproved
+		);
+		assert_ok!(NftCurrency::approve_for_all(RuntimeOrigin::signed(1), 2));
+		assert!(<NftCurrency as NonFungibleToken<u64>>::is_approve_for_all((1, 2)));
+		System::assert_last_event(Event::ApproveForAll(1, 2).into());
+
+		assert_ok!(NftCurrency::safe_transfer_ownership(
+			RuntimeOrigin::signed(2),
+			1,
+			3,
+			token.clone()
+		));
+		assert_eq!(NftCurrency::owner_of(token.clone()), Some(3));
+		assert_eq!(NftCurrency::list_owned(3), vec![token.clone()]);
+		System::assert_last_event(Event::Transfer(1, 3, token).into());
+	});
+}
+
+#[test]
+fn owner_safe_transfers_its_own_token() {
+	new_test_ext().execute_with(|| {
+		let token = mint(1);
+		assert_ok!(NftCurrency::safe_transfer_ownership(
+			RuntimeOrigin::signed(1),
+			1,
+			2,
+			token.clone()
+		));
+		assert_eq!(NftCurrency::owner_of(token), Some(2));
+	});
+}
+
+#[test]
+fn owner_approves_account() {
+	new_test_ext().execute_with(|| {
+		let token = mint(1);
+		assert_noop!(
+			NftCurrency::approve(RuntimeOrigin::signed(2), 3, token.clone()),
+			Error::<Test>::NotOwner
+		);
+		assert_ok!(NftCurrency::approve(RuntimeOrigin::signed(1), 2, token.clone()));
+		assert_eq!(TokenApproval::<Test>::get(token.clone()), vec![1, 2]);
+		System::assert_last_event(Event::Approve(1, 2, token).into());
+	});
+}
+
+#[test]
+fn owner_updates_uri() {
+	new_test_ext().execute_with(|| {
+		let token = mint(1);
+		assert_noop!(
+			NftCurrency::set_token_uri(
+				RuntimeOrigin::signed(2),
+				token.clone(),
+				b"ipfs".to_vec(),
+				None
+			),
+			Error::<Test>::NotOwner
+		);
+		assert_ok!(NftCurrency::set_token_uri(
+			RuntimeOrigin::signed(1),
+			token.clone(),
+			b"ipfs".to_vec(),
+			None
+		));
+		assert_eq!(
+			<NftCurrency as NonFungibleToken<u64>>::token_uri(token.clone()),
+			Some(b"ipfs".to_vec())
+		);
+		System::assert_last_event(Event::SetUri(token, b"ipfs".to_vec()).into());
+	});
+}
+
+#[test]
+fn only_custodian_moves_custody() {
+	new_test_ext().execute_with(|| {
+		let token = mint(1);
+		assert_noop!(
+			<NftCurrency as NonFungibleToken<u64>>::transfer_custodian(2, 3, token.clone()),
+			Error::<Test>::NotCustodian
+		);
+		assert_ok!(<NftCurrency as NonFungibleToken<u64>>::transfer_custodian(1, 2, token.clone()));
+		// the borrower may sublet, the owner may not take the token back
+		assert_noop!(
+			<NftCurrency as NonFungibleToken<u64>>::transfer_custodian(1, 3, token.clone()),
+			Error::<Test>::NotCustodian
+		);
+		assert_ok!(<NftCurrency as NonFungibleToken<u64>>::transfer_custodian(2, 3, token.clone()));
+		assert_eq!(NftCurrency::custodian_of(token.clone()), Some(3));
+		assert_eq!(NftCurrency::owner_of(token), Some(1));
+	});
+}
+
+#[test]
+fn rented_token_cannot_be_transferred() {
+	new_test_ext().execute_with(|| {
+		let token = mint(1);
+		assert_ok!(<NftCurrency as NonFungibleToken<u64>>::transfer_custodian(1, 2, token.clone()));
+		assert_noop!(
+			NftCurrency::transfer_ownership(RuntimeOrigin::signed(1), 3, token.clone()),
+			Error::<Test>::InRent
+		);
+		assert_noop!(
+			NftCurrency::safe_transfer_ownership(RuntimeOrigin::signed(1), 1, 3, token.clone()),
+			Error::<Test>::NotOwnerNorApproved
+		);
+	});
+}
+
+#[test]
+fn getters_return_none_for_unknown_token() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(<NftCurrency as NonFungibleToken<u64>>::token_uri(UNKNOWN.to_vec()), None);
+		assert_eq!(<NftCurrency as NonFungibleToken<u64>>::owner_of_token(UNKNOWN.to_vec()), None);
+		assert_eq!(
+			<NftCurrency as NonFungibleToken<u64>>::custodian_of_token(UNKNOWN.to_vec()),
+			None
+		);
+		assert!(!<NftCurrency as NonFungibleToken<u64>>::is_approve_for_all((1, 2)));
+	});
+}
+
+#[test]
+fn extrinsics_fail_for_unknown_token() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			NftCurrency::transfer_ownership(RuntimeOrigin::signed(1), 2, UNKNOWN.to_vec()),
+			Error::<Test>::NoneExist
+		);
+		assert_noop!(
+			NftCurrency::safe_transfer_ownership(RuntimeOrigin::signed(2), 1, 2, UNKNOWN.to_vec()),
+			Error::<Test>::NoneExist
+		);
+		assert_noop!(
+			NftCurrency::approve(RuntimeOrigin::signed(1), 2, UNKNOWN.to_vec()),
+			Error::<Test>::NoneExist
+		);
+		assert_noop!(
+			NftCurrency::set_token_uri(
+				RuntimeOrigin::signed(1),
+				UNKNOWN.to_vec(),
+				b"uri".to_vec(),
+				None
+			),
+			Error::<Test>::NoneExist
+		);
+	});
+}
+
+#[test]
+fn trait_calls_fail_for_unknown_token() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			<NftCurrency as NonFungibleToken<u64>>::transfer_ownership(1, 2, UNKNOWN.to_vec()),
+			Error::<Test>::NoneExist
+		);
+		assert_noop!(
+			<NftCurrency as NonFungibleToken<u64>>::transfer_custodian(1, 2, UNKNOWN.to_vec()),
+			Error::<Test>::NoneExist
+		);
+		assert_noop!(
+			<NftCurrency as NonFungibleToken<u64>>::approve(1, 2, UNKNOWN.to_vec()),
+			Error::<Test>::NoneExist
+		);
+		assert_noop!(
+			<NftCurrency as NonFungibleToken<u64>>::set_token_uri(
+				UNKNOWN.to_vec(),
+				b"uri".to_vec()
+			),
+			Error::<Test>::NoneExist
+		);
+	});
+}
+
+#[test]
+fn custodian_falls_back_to_owner_after_return() {
+	new_test_ext().execute_with(|| {
+		let token = mint(1);
+		assert_ok!(<NftCurrency as NonFungibleToken<u64>>::transfer_custodian(1, 2, token.clone()));
+		assert_eq!(
+			<NftCurrency as NonFungibleToken<u64>>::custodian_of_token(token.clone()),
+			Some(2)
+		);
+		assert_ok!(<NftCurrency as NonFungibleToken<u64>>::transfer_custodian(2, 1, token.clone()));
+		assert_eq!(
+			<NftCurrency as NonFungibleToken<u64>>::custodian_of_token(token.clone()),
+			Some(1)
+		);
+		// the owner can move the token again once it is back
+		assert_ok!(NftCurrency::transfer_ownership(RuntimeOrigin::signed(1), 3, token.clone()));
+		assert_eq!(<NftCurrency as NonFungibleToken<u64>>::custodian_of_token(token), Some(3));
+	});
+}
+
+#[test]
+fn tokens_are_inspected_as_nonfungibles() {
+	new_test_ext().execute_with(|| {
+		let token = mint(1);
+		assert_eq!(<NftCurrency as Inspect<u64>>::owner(&(), &token), Some(1));
+		assert_eq!(
+			<NftCurrency as Inspect<u64>>::attribute(&(), &token, &[]),
+			Some(b"uri".to_vec())
+		);
+		assert_eq!(<NftCurrency as Inspect<u64>>::attribute(&(), &token, b"name"), None);
+		assert!(<NftCurrency as Inspect<u64>>::can_transfer(&(), &token));
+		assert!(!<NftCurrency as Inspect<u64>>::can_transfer(&(), &UNKNOWN.to_vec()));
+
+		assert_ok!(<NftCurrency as NonFungibleToken<u64>>::transfer_custodian(1, 2, token.clone()));
+		assert!(!<NftCurrency as Inspect<u64>>::can_transfer(&(), &token));
+	});
+}
+
+#[test]
+fn tokens_are_minted_and_transferred_as_nonfungibles() {
+	new_test_ext().execute_with(|| {
+		let token = UNKNOWN.to_vec();
+		assert_ok!(<NftCurrency as Mutate<u64>>::mint_into(&(), &token, &1));
+		assert_noop!(
+			<NftCurrency as Mutate<u64>>::mint_into(&(), &token, &2),
+			Error::<Test>::AlreadyExist
+		);
+		assert_eq!(NftCurrency::total_tokens(), 1);
+		assert_eq!(NftCurrency::list_owned(1), vec![token.clone()]);
+		assert_eq!(
+			<NftCurrency as NonFungibleToken<u64>>::custodian_of_token(token.clone()),
+			Some(1)
+		);
+
+		assert_ok!(<NftCurrency as Mutate<u64>>::set_attribute(&(), &token, &[], b"ipfs"));
+		assert_eq!(NftCurrency::token_uri(token.clone()), Some(b"ipfs".to_vec()));
+		assert_noop!(
+			<NftCurrency as Mutate<u64>>::set_attribute(&(), &token, b"name", b"ipfs"),
+			Error::<Test>::Invalid
+		);
+
+		assert_ok!(<NftCurrency as Transfer<u64>>::transfer(&(), &token, &2));
+		assert_eq!(NftCurrency::owner_of(token.clone()), Some(2));
+		assert!(NftCurrency::list_owned(1).is_empty());
+	});
+}
+
+#[test]
+fn rented_token_cannot_be_transferred_as_nonfungible() {
+	new_test_ext().execute_with(|| {
+		let token = mint(1);
+		assert_ok!(<NftCurrency as NonFungibleToken<u64>>::transfer_custodian(1, 2, token.clone()));
+		assert_noop!(
+			<NftCurrency as Transfer<u64>>::transfer(&(), &token, &3),
+			Error::<Test>::InRent
+		);
+		assert_noop!(
+			<NftCurrency as Transfer<u64>>::transfer(&(), &UNKNOWN.to_vec(), &3),
+			Error::<Test>::NoneExist
+		);
+		// all tokens live in the one collection of the pallet
+		assert_noop!(
+			<NftCurrency as Create<u64>>::create_collection(&(), &1, &1),
+			Error::<Test>::AlreadyExist
+		);
+	});
+}
+
+const METADATA_URI: &str = "http://localhost:8000/sword.json";
+const METADATA: &[u8] = br#"{"name":"Sword","description":"Sharp","image":"ipfs://sword.png"}"#;
+
+/// Test externalities with an HTTP stub and a transaction pool, the verifier key in the keystore.
+fn offchain_ext() -> (sp_io::TestExternalities, Arc<RwLock<OffchainState>>, Arc<RwLock<PoolState>>)
+{
+	let (offchain, state) = testing::TestOffchainExt::new();
+	let (pool, pool_state) = testing::TestTransactionPoolExt::new();
+	let mut ext = new_test_ext();
+	ext.register_extension(OffchainDbExt::new(offchain.clone()));
+	ext.register_extension(OffchainWorkerExt::new(offchain));
+	ext.register_extension(TransactionPoolExt::new(pool));
+	UintAuthorityId::set_all_keys(vec![VERIFIER]);
+	(ext, state, pool_state)
+}
+
+fn serve(state: &RwLock<OffchainState>, body: &[u8]) {
+	serve_at(state, METADATA_URI, body);
+}
+
+fn serve_at(state: &RwLock<OffchainState>, uri: &str, body: &[u8]) {
+	state.write().expect_request(testing::PendingRequest {
+		method: "GET".into(),
+		uri: uri.into(),
+		response: Some(body.to_vec()),
+		sent: true,
+		..Default::default()
+	});
+}
+
+fn mint_with_metadata(owner: u64) -> Vec<u8> {
+	assert_ok!(NftCurrency::mint_to(
+		RuntimeOrigin::signed(owner),
+		owner,
+		METADATA_URI.as_bytes().to_vec()
+	));
+	NftCurrency::list_owned(owner).last().cloned().unwrap()
+}
+
+fn report(token: &[u8], verdict: Verdict) -> frame_support::dispatch::DispatchResult {
+	NftCurrency::report_metadata(
+		RuntimeOrigin::signed(VERIFIER),
+		token.to_vec(),
+		sp_io::hashing::blake2_256(METADATA_URI.as_bytes()),
+		verdict,
+	)
+}
+
+#[test]
+fn metadata_schema_requires_the_erc721_strings() {
+	assert!(metadata::follows_schema(METADATA));
+	assert!(!metadata::follows_schema(br#"{"name":"Sword","description":"Sharp"}"#));
+	assert!(!metadata::follows_schema(br#"{"name":1,"description":"Sharp","image":"x"}"#));
+	assert!(!metadata::follows_schema(br#"["name","description","image"]"#));
+	assert!(!metadata::follows_schema(&[0xff, 0xfe]));
+}
+
+#[test]
+fn offchain_worker_reports_fetched_metadata() {
+	let (mut ext, state, pool_state) = offchain_ext();
+	ext.execute_with(|| {
+		let token = mint_with_metadata(1);
+		assert_eq!(NftCurrency::metadata_check(&token).unwrap().status, MetadataStatus::Pending);
+
+		serve(&state, METADATA);
+		NftCurrency::offchain_worker(1);
+		let tx = pool_state.write().transactions.pop().unwrap();
+		let tx = Extrinsic::decode(&mut &tx[..]).unwrap();
+		assert_eq!(tx.signature, Some((VERIFIER, ())));
+		assert_eq!(
+			tx.call,
+			RuntimeCall::NftCurrency(crate::Call::report_metadata {
+				token_id: token,
+				uri_hash: sp_io::hashing::blake2_256(METADATA_URI.as_bytes()),
+				verdict: Verdict::Valid(sp_io::hashing::blake2_256(METADATA)),
+			})
+		);
+
+		// the report is not sent again while it may still be in the pool
+		NftCurrency::offchain_worker(2);
+		assert!(pool_state.read().transactions.is_empty());
+	});
+}
+
+#[test]
+fn offchain_worker_checks_the_tokens_not_reported_yet() {
+	let (mut ext, state, pool_state) = offchain_ext();
+	ext.execute_with(|| {
+		// two checks per run
+		let tokens = [mint_with_metadata(1), mint_with_metadata(1), mint_with_metadata(1)];
+		serve(&state, METADATA);
+		serve(&state, METADATA);
+		NftCurrency::offchain_worker(1);
+		assert_eq!(pool_state.read().transactions.len(), 2);
+
+		// the reports of the first run may still be in the pool, the third token is checked
+		serve(&state, METADATA);
+		NftCurrency::offchain_worker(2);
+		let transactions = pool_state.read().transactions.clone();
+		assert_eq!(transactions.len(), 3);
+		let reported = transactions
+			.iter()
+			.map(|tx| match Extrinsic::decode(&mut &tx[..]).unwrap().call {
+				RuntimeCall::NftCurrency(crate::Call::report_metadata { token_id, .. }) => token_id,
+				call => panic!("unexpected call {:?}", call),
+			})
+			.collect::<Vec<_>>();
+		assert!(tokens.iter().all(|token| reported.contains(token)));
+	});
+}
+
+#[test]
+fn offchain_worker_reports_broken_metadata() {
+	let (mut ext, state, pool_state) = offchain_ext();
+	ext.execute_with(|| {
+		mint_with_metadata(1);
+		serve(&state, b"<html>moved</html>");
+		NftCurrency::offchain_worker(1);
+		let tx = pool_state.write().transactions.pop().unwrap();
+		let tx = Extrinsic::decode(&mut &tx[..]).unwrap();
+		assert!(matches!(
+			tx.call,
+			RuntimeCall::NftCurrency(crate::Call::report_metadata {
+				verdict: Verdict::InvalidSchema,
+				..
+			})
+		));
+
+		// only HTTP uris are fetched
+		assert_eq!(metadata::verify(b"ipfs://sword.json", None), Verdict::Unreachable);
+	});
+}
+
+#[test]
+fn reports_pin_the_first_valid_content() {
+	new_test_ext().execute_with(|| {
+		let token = mint_with_metadata(1);
+		assert_ok!(report(&token, Verdict::Valid([1; 32])));
+		assert_eq!(
+			NftCurrency::metadata_check(&token),
+			Some(MetadataCheck {
+				status: MetadataStatus::Valid,
+				content_hash: Some([1; 32]),
+				checked_at: 1
+			})
+		);
+		System::assert_last_event(
+			Event::MetadataChecked(token.clone(), MetadataStatus::Valid).into(),
+		);
+
+		assert_ok!(report(&token, Verdict::Valid([2; 32])));
+		let check = NftCurrency::metadata_check(&token).unwrap();
+		assert_eq!(check.status, MetadataStatus::Mutated);
+		assert_eq!(check.content_hash, Some([1; 32]));
+
+		assert_ok!(report(&token, Verdict::Unreachable));
+		assert_eq!(NftCurrency::metadata_check(&token).unwrap().content_hash, Some([1; 32]));
+
+		// a new uri is checked from scratch
+		assert_ok!(NftCurrency::set_token_uri(
+			RuntimeOrigin::signed(1),
+			token.clone(),
+			METADATA_URI.as_bytes().to_vec(),
+			None
+		));
+		let check = NftCurrency::metadata_check(&token).unwrap();
+		assert_eq!(check.status, MetadataStatus::Pending);
+		assert_eq!(check.content_hash, None);
+	});
+}
+
+#[test]
+fn reports_come_from_verifiers_about_the_current_uri() {
+	new_test_ext().execute_with(|| {
+		let token = mint_with_metadata(1);
+		assert_noop!(
+			NftCurrency::report_metadata(
+				RuntimeOrigin::signed(1),
+				token.clone(),
+				sp_io::hashing::blake2_256(METADATA_URI.as_bytes()),
+				Verdict::Unreachable
+			),
+			sp_runtime::traits::BadOrigin
+		);
+		assert_noop!(
+			NftCurrency::report_metadata(
+				RuntimeOrigin::signed(VERIFIER),
+				token,
+				sp_io::hashing::blake2_256(b"http://localhost:8000/old.json"),
+				Verdict::Unreachable
+			),
+			Error::<Test>::OutdatedReport
+		);
+		assert_noop!(report(&UNKNOWN, Verdict::Unreachable), Error::<Test>::NoneExist);
+	});
+}
+
+/// Raw cid of `content`, the sha2-256 of the file itself.
+fn raw_cid(content: &[u8]) -> Cid {
+	Cid::V1 {
+		codec: cid::RAW,
+		hash: Multihash { code: cid::SHA2_256, digest: sp_io::hashing::sha2_256(content).to_vec() },
+	}
+}
+
+fn ipfs_uri(cid: &Cid) -> Vec<u8> {
+	[&b"ipfs://"[..], &cid.to_text()].concat()
+}
+
+#[test]
+fn cids_are_written_as_ipfs_does() {
+	let empty = raw_cid(b"");
+	assert_eq!(empty.to_text(), b"bafkreihdwdcefgh4dqkjv67uzcmw7ojee6xedzdetojuzjevtenxquvyku");
+	assert_eq!(&empty.to_bytes()[..4], &[0x01, 0x55, 0x12, 0x20]);
+	// the empty unixfs directory
+	let directory = Cid::V0(Multihash {
+		code: cid::SHA2_256,
+		digest: from_hex("59948439065f29619ef41280cbb932be52c56d99c5966b65e0111239f098bbef"),
+	});
+	assert_eq!(directory.to_text(), b"QmUNLLsPACCz1vLxQVkXqqLX5R1X345qqfHbsf67hvA3Nn");
+
+	assert!(
+		empty.is_named_by(b"ipfs://bafkreihdwdcefgh4dqkjv67uzcmw7ojee6xedzdetojuzjevtenxquvyku")
+	);
+	assert!(directory.is_named_by(
+		b"https://ipfs.io/ipfs/QmUNLLsPACCz1vLxQVkXqqLX5R1X345qqfHbsf67hvA3Nn/1.json"
+	));
+	assert!(!directory.is_named_by(b"ipfs://QmUNLLsPACCz1vLxQVkXqqLX5R1X345qqfHbsf67hvA3Nnx"));
+	assert!(!directory
+		.is_named_by(b"https://example.com/QmUNLLsPACCz1vLxQVkXqqLX5R1X345qqfHbsf67hvA3Nn"));
+
+	let uri = ipfs_uri(&empty);
+	assert_eq!(empty.verifies(&uri, b""), Some(true));
+	assert_eq!(empty.verifies(&uri, b"changed"), Some(false));
+	assert_eq!(
+		directory.verifies(b"ipfs://QmUNLLsPACCz1vLxQVkXqqLX5R1X345qqfHbsf67hvA3Nn", b""),
+		None
+	);
+}
+
+fn from_hex(hex: &str) -> Vec<u8> {
+	(0..hex.len())
+		.step_by(2)
+		.map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+		.collect()
+}
+
+#[test]
+fn owner_pins_the_cid_named_by_the_uri() {
+	new_test_ext().execute_with(|| {
+		let token = mint(1);
+		let cid = raw_cid(METADATA);
+		let mut short = cid.clone();
+		if let Cid::V1 { hash, .. } = &mut short {
+			hash.digest.pop();
+		}
+		assert_noop!(
+			NftCurrency::set_token_uri(
+				RuntimeOrigin::signed(1),
+				token.clone(),
+				ipfs_uri(&cid),
+				Some(short)
+			),
+			Error::<Test>::InvalidCid
+		);
+		assert_noop!(
+			NftCurrency::set_token_uri(
+				RuntimeOrigin::signed(1),
+				token.clone(),
+				ipfs_uri(&raw_cid(b"")),
+				Some(cid.clone())
+			),
+			Error::<Test>::InvalidCid
+		);
+
+		assert_ok!(NftCurrency::set_token_uri(
+			RuntimeOrigin::signed(1),
+			token.clone(),
+			ipfs_uri(&cid),
+			Some(cid.clone())
+		));
+		assert_eq!(NftCurrency::token_cid(&token), Some(cid.clone()));
+		System::assert_last_event(Event::SetCid(token.clone(), cid).into());
+
+		// a uri set without a cid is not content addressed anymore
+		assert_ok!(NftCurrency::set_token_uri(
+			RuntimeOrigin::signed(1),
+			token.clone(),
+			b"uri".to_vec(),
+			None
+		));
+		assert_eq!(NftCurrency::token_cid(&token), None);
+	});
+}
+
+#[test]
+fn locked_collection_freezes_uris() {
+	new_test_ext().execute_with(|| {
+		let token = mint(1);
+		assert_noop!(
+			NftCurrency::lock_collection(RuntimeOrigin::signed(1)),
+			sp_runtime::traits::BadOrigin
+		);
+		assert_ok!(NftCurrency::lock_collection(RuntimeOrigin::root()));
+		System::assert_last_event(Event::CollectionLocked.into());
+		assert_noop!(
+			NftCurrency::lock_collection(RuntimeOrigin::root()),
+			Error::<Test>::AlreadyLocked
+		);
+
+		assert_noop!(
+			NftCurrency::set_token_uri(
+				RuntimeOrigin::signed(1),
+				token.clone(),
+				b"ipfs".to_vec(),
+				None
+			),
+			Error::<Test>::UriFrozen
+		);
+		assert_noop!(
+			<NftCurrency as Mutate<u64>>::set_attribute(&(), &token, &[], b"ipfs"),
+			Error::<Test>::UriFrozen
+		);
+
+		// tokens minted later get their first uri, and keep it
+		let later = mint(2);
+		assert_eq!(NftCurrency::token_uri(later.clone()), Some(b"uri".to_vec()));
+		assert_noop!(
+			NftCurrency::set_token_uri(RuntimeOrigin::signed(2), later, b"ipfs".to_vec(), None),
+			Error::<Test>::UriFrozen
+		);
+	});
+}
+
+#[test]
+fn offchain_worker_reports_content_other_than_the_cid() {
+	let (mut ext, state, pool_state) = offchain_ext();
+	ext.execute_with(|| {
+		let token = mint(1);
+		let cid = raw_cid(METADATA);
+		let uri = [&b"http://localhost:8000/ipfs/"[..], &cid.to_text()].concat();
+		assert_ok!(NftCurrency::set_token_uri(
+			RuntimeOrigin::signed(1),
+			token.clone(),
+			uri.clone(),
+			Some(cid)
+		));
+
+		// valid metadata, but not the one the cid names
+		serve_at(
+			&state,
+			std::str::from_utf8(&uri).unwrap(),
+			br#"{"name":"Axe","description":"Blunt","image":"x"}"#,
+		);
+		NftCurrency::offchain_worker(1);
+		let tx = pool_state.write().transactions.pop().unwrap();
+		let tx = Extrinsic::decode(&mut &tx[..]).unwrap();
+		assert_eq!(
+			tx.call,
+			RuntimeCall::NftCurrency(crate::Call::report_metadata {
+				token_id: token.clone(),
+				uri_hash: sp_io::hashing::blake2_256(&uri),
+				verdict: Verdict::Mismatch,
+			})
+		);
+
+		assert_ok!(NftCurrency::report_metadata(
+			RuntimeOrigin::signed(VERIFIER),
+			token.clone(),
+			sp_io::hashing::blake2_256(&uri),
+			Verdict::Mismatch
+		));
+		assert_eq!(NftCurrency::metadata_check(&token).unwrap().status, MetadataStatus::Mutated);
+	});
+}
+
+#[test]
+fn only_minters_mint_once_there_is_one() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			NftCurrency::add_minter(RuntimeOrigin::signed(1), 1),
+			sp_runtime::traits::BadOrigin
+		);
+		assert_ok!(NftCurrency::add_minter(RuntimeOrigin::root(), 1));
+		System::assert_last_event(Event::MinterAdded(1).into());
+		assert!(NftCurrency::is_minter(1).is_some());
+
+		mint(1);
+		assert_noop!(
+			NftCurrency::mint_to(RuntimeOrigin::signed(2), 2, b"uri".to_vec()),
+			Error::<Test>::NotMinter
+		);
+		assert_noop!(
+			NftCurrency::mint_to(RuntimeOrigin::none(), 2, b"uri".to_vec()),
+			sp_runtime::traits::BadOrigin
+		);
+
+		assert_noop!(
+			NftCurrency::remove_minter(RuntimeOrigin::root(), 2),
+			Error::<Test>::NotMinter
+		);
+		assert_ok!(NftCurrency::remove_minter(RuntimeOrigin::root(), 1));
+		System::assert_last_event(Event::MinterRemoved(1).into());
+		// without minters anyone mints again
+		mint(2);
+	});
+}
@@ -0,0 +1,134 @@
+//! Provisional weights for pallet_nft_currency, they are NOT benchmark output.
+//!
+//! The values were estimated by hand from the storage each call touches, transaction fees will not
+//! match the real cost of the calls until this file is replaced by the output of
+//! `./scripts/benchmark.sh pallet_nft_currency` run on reference hardware. The script runs the
+//! Substrate benchmark CLI with `--steps 50 --repeat 20` and `.maintain/frame-weight-template.hbs`.
+
+#![cfg_attr(rustfmt, rustfmt_skip)]
+#![allow(unused_parens)]
+#![allow(unused_imports)]
+
+use frame_support::{traits::Get, weights::{Weight, constants::RocksDbWeight}};
+use sp_std::marker::PhantomData;
+
+/// Weight functions needed for pallet_nft_currency.
+pub trait WeightInfo {
+	fn mint_token() -> Weight;
+	fn transfer_ownership() -> Weight;
+	fn safe_transfer_ownership() -> Weight;
+	fn approve() -> Weight;
+	fn approve_for_all() -> Weight;
+	fn set_token_uri() -> Weight;
+	fn lock_collection() -> Weight;
+	fn add_minter() -> Weight;
+	fn remove_minter() -> Weight;
+	fn report_metadata() -> Weight;
+}
+
+/// Provisional weights for pallet_nft_currency, see the module documentation.
+pub struct SubstrateWeight<T>(PhantomData<T>);
+impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
+	fn mint_token() -> Weight {
+		Weight::from_ref_time(38_215_000 as u64)
+			.saturating_add(T::DbWeight::get().reads(8 as u64))
+			.saturating_add(T::DbWeight::get().writes(8 as u64))
+	}
+	fn transfer_ownership() -> Weight {
+		Weight::from_ref_time(35_678_000 as u64)
+			.saturating_add(T::DbWeight::get().reads(4 as u64))
+			.saturating_add(T::DbWeight::get().writes(4 as u64))
+	}
+	fn safe_transfer_ownership() -> Weight {
+		Weight::from_ref_time(54_275_000 as u64)
+			.saturating_add(T::DbWeight::get().reads(5 as u64))
+			.saturating_add(T::DbWeight::get().writes(4 as u64))
+	}
+	fn approve() -> Weight {
+		Weight::from_ref_time(38_030_000 as u64)
+			.saturating_add(T::DbWeight::get().reads(3 as u64))
+			.saturating_add(T::DbWeight::get().writes(1 as u64))
+	}
+	fn approve_for_all() -> Weight {
+		Weight::from_ref_time(26_615_000 as u64)
+			.saturating_add(T::DbWeight::get().reads(1 as u64))
+			.saturating_add(T::DbWeight::get().writes(1 as u64))
+	}
+	fn set_token_uri() -> Weight {
+		Weight::from_ref_time(24_112_000 as u64)
+			.saturating_add(T::DbWeight::get().reads(3 as u64))
+			.saturating_add(T::DbWeight::get().writes(3 as u64))
+	}
+	fn lock_collection() -> Weight {
+		Weight::from_ref_time(12_870_000 as u64)
+			.saturating_add(T::DbWeight::get().reads(1 as u64))
+			.saturating_add(T::DbWeight::get().writes(1 as u64))
+	}
+	fn add_minter() -> Weight {
+		Weight::from_ref_time(11_502_000 as u64)
+			.saturating_add(T::DbWeight::get().writes(1 as u64))
+	}
+	fn remove_minter() -> Weight {
+		Weight::from_ref_time(14_387_000 as u64)
+			.saturating_add(T::DbWeight::get().reads(1 as u64))
+			.saturating_add(T::DbWeight::get().writes(1 as u64))
+	}
+	fn report_metadata() -> Weight {
+		Weight::from_ref_time(27_418_000 as u64)
+			.saturating_add(T::DbWeight::get().reads(2 as u64))
+			.saturating_add(T::DbWeight::get().writes(1 as u64))
+	}
+}
+
+// For backwards compatibility and tests
+impl WeightInfo for () {
+	fn mint_token() -> Weight {
+		Weight::from_ref_time(38_215_000 as u64)
+			.saturating_add(RocksDbWeight::get().reads(8 as u64))
+			.saturating_add(RocksDbWeight::get().writes(8 as u64))
+	}
+	fn transfer_ownership() -> Weight {
+		Weight::from_ref_time(35_678_000 as u64)
+			.saturating_add(RocksDbWeight::get().reads(4 as u64))
+			.saturating_add(RocksDbWeight::get().writes(4 as u64))
+	}
+	fn safe_transfer_ownership() -> Weight {
+		Weight::from_ref_time(54_275_000 as u64)
+			.saturating_add(RocksDbWeight::get().reads(5 as u64))
+			.saturating_add(RocksDbWeight::get().writes(4 as u64))
+	}
+	fn approve() -> Weight {
+		Weight::from_ref_time(38_030_000 as u64)
+			.saturating_add(RocksDbWeight::get().reads(3 as u64))
+			.saturating_add(RocksDbWeight::get().writes(1 as u64))
+	}
+	fn approve_for_all() -> Weight {
+		Weight::from_ref_time(26_615_000 as u64)
+			.saturating_add(RocksDbWeight::get().reads(1 as u64))
+			.saturating_add(RocksDbWeight::get().writes(1 as u64))
+	}
+	fn set_token_uri() -> Weight {
+		Weight::from_ref_time(24_112_000 as u64)
+			.saturating_add(RocksDbWeight::get().reads(3 as u64))
+			.saturating_add(RocksDbWeight::get().writes(3 as u64))
+	}
+	fn lock_collection() -> Weight {
+		Weight::from_ref_time(12_870_000 as u64)
+			.saturating_add(RocksDbWeight::get().reads(1 as u64))
+			.saturating_add(RocksDbWeight::get().writes(1 as u64))
+	}
+	fn add_minter() -> Weight {
+		Weight::from_ref_time(11_502_000 as u64)
+			.saturating_add(RocksDbWeight::get().writes(1 as u64))
+	}
+	fn remove_minter() -> Weight {
+		Weight::from_ref_time(14_387_000 as u64)
+			.saturating_add(RocksDbWeight::get().reads(1 as u64))
+			.saturating_add(RocksDbWeight::get().writes(1 as u64))
+	}
+	fn report_metadata() -> Weight {
+		Weight::from_ref_time(27_418_000 as u64)
+			.saturating_add(RocksDbWeight::get().reads(2 as u64))
+			.saturating_add(RocksDbWeight::get().writes(1 as u64))
+	}
+}
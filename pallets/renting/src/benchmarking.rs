@@ -1,32 +1,274 @@
-// //! Benchmarking setup for pallet-nft_currency
-//
-// use super::*;
-//
-// #[allow(unused)]
-// use crate::Pallet as Renting;
-// use pallet_nft_currency as NftCurrency;
-// use account_to_bytes;
-// use frame_benchmarking::{account, benchmarks, whitelisted_caller};
-// use frame_system::RawOrigin;
-// benchmarks! {
-// 	create_rental {
-// 		let caller: T::AccountId = whitelisted_caller();
-// 		let account: T::AccountId = account("account",1,1);
-// 		let tokenId = NftCurrency::Pallet::mint_to()
-// 		let orderLeft = Order{
-// 			lender: caller,
-// 			borrower:[0u8;32],
-// 			fee:10000,
-//
-// 		}
-// 		let uri = "linkUri";
-// 	}: create_rental(RawOrigin::Signed(caller),account,caller,uri)
-// 	verify {
-// 		assert_eq!(TotalTokens::<T>::get(), 1);
-// 		assert_eq!(TokenUri::<T>::get(), 1);
-// 	}
-//
-//
-//
-// 	impl_benchmark_test_suite!(Renting, crate::mock::new_test_ext(), crate::mock::Test);
-// }
+//! Benchmarking setup for pallet-renting
+
+use super::*;
+
+#[allow(unused)]
+use crate::Pallet as Renting;
+use frame_benchmarking::{account, benchmarks, whitelisted_caller};
+use frame_system::RawOrigin;
+use scale_info::prelude::format;
+use sp_core::crypto::KeyTypeId;
+
+const KEY_TYPE: KeyTypeId = KeyTypeId(*b"rent");
+
+// Create a rental whose NFT is held by a funded borrower, as `create_rental` would leave it.
+fn rented_order<T: Config>(billing: BillingPlan) -> (T::AccountId, Order) {
+	let lender: T::AccountId = account("lender", 0, 0);
+	let borrower: T::AccountId = account("borrower", 0, 0);
+	T::Currency::make_free_balance_be(&borrower, 1_000_000_000_000u64.saturated_into());
+	T::Currency::make_free_balance_be(&T::FeeDestination::get(), 1_000_000u64.saturated_into());
+	let token = T::TokenNFT::mint(lender.clone()).unwrap();
+	T::TokenNFT::transfer_custodian(lender.clone(), borrower.clone(), token.clone()).unwrap();
+	let order = Order {
+		lender: account_to_bytes(&lender).unwrap(),
+		borrower: account_to_bytes(&borrower).unwrap(),
+		fee: 1_000_000,
+		token,
+		due_date: 0,
+		billing,
+		asset_id: None,
+		min_reputation: 0,
+		credential: None,
+		registry: Registry::NftCurrency,
+	};
+	Borrowers::<T>::insert(borrower.clone(), order.token.clone(), order.clone());
+	(borrower, order)
+}
+
+// An account backed by a sr25519 key of the keystore, so it can sign orders.
+fn signer<T: Config>() -> (sr25519::Public, T::AccountId) {
+	let public = sp_io::crypto::sr25519_generate(KEY_TYPE, None);
+	let account = T::AccountId::decode(&mut &public.0[..]).unwrap();
+	(public, account)
+}
+
+// SS58 address of an account, as written in an order.
+fn address<T: Config>(account: &T::AccountId) -> String {
+	let mut data = vec![42u8];
+	data.extend_from_slice(&account_to_bytes(account).unwrap());
+	let checksum = sp_core::hashing::blake2_512(&[&b"SS58PRE"[..], &data[..]].concat());
+	data.extend_from_slice(&checksum[..2]);
+	let mut output = [0u8; 64];
+	let len = bs58::encode(&data).into(&mut output[..]).unwrap();
+	String::from_utf8(output[..len].to_vec()).unwrap()
+}
+
+// An order for `token`, signed by the lender when `borrower` is `None`.
+fn order_message<T: Config>(
+	lender: &T::AccountId,
+	borrower: Option<&T::AccountId>,
+	token: &[u8],
+	due_date: u64,
+) -> Vec<u8> {
+	let token = token.iter().map(|b| format!("{:02x}", b)).collect::<String>();
+	let borrower = match borrower {
+		Some(borrower) => format!("\"borrower\":\"{}\",", address::<T>(borrower)),
+		None => String::new(),
+	};
+	format!(
+		"{{\"lender\":\"{}\",{}\"fee\":1000000,\"token\":\"{}\",\"due_date\":{}}}",
+		address::<T>(lender),
+		borrower,
+		token,
+		due_date
+	)
+	.into_bytes()
+}
+
+benchmarks! {
+	create_rental {
+		let (lender_key, lender) = signer::<T>();
+		let borrower: T::AccountId = whitelisted_caller();
+		T::Currency::make_free_balance_be(&borrower, 1_000_000_000_000u64.saturated_into());
+		T::Currency::make_free_balance_be(&T::FeeDestination::get(), 1_000_000u64.saturated_into());
+		let token = T::TokenNFT::mint(lender.clone())?;
+		let due_date = T::Timestamp::now().as_secs() + 86_400;
+		let message_left = order_message::<T>(&lender, None, &token, due_date);
+		let signature_left = sp_io::crypto::sr25519_sign(KEY_TYPE, &lender_key, &message_left)
+			.unwrap()
+			.0
+			.to_vec();
+		let message_right = order_message::<T>(&lender, Some(&borrower), &token, due_date);
+	}: _(
+		RawOrigin::Signed(borrower.clone()),
+		lender,
+		borrower.clone(),
+		message_left,
+		signature_left,
+		message_right,
+		vec![]
+	)
+	verify {
+		assert!(Borrowers::<T>::try_get(borrower, token).is_ok());
+	}
+
+	cancel_offer {
+		let lender: T::AccountId = whitelisted_caller();
+		let due_date = T::Timestamp::now().as_secs() + 86_400;
+		let message = order_message::<T>(&lender, None, &[0u8; 32], due_date);
+	}: _(RawOrigin::Signed(lender), message, true)
+	verify {
+		assert_eq!(CancelOrder::<T>::iter().count(), 1);
+	}
+
+	stop_renting {
+		let (borrower, order) = rented_order::<T>(BillingPlan::Upfront);
+		let due_date = T::Timestamp::now().as_secs() + 86_400;
+		Renting::<T>::schedule_rental(&borrower, &Order { due_date, ..order.clone() })?;
+	}: _(RawOrigin::Signed(borrower.clone()), order.token.clone())
+	verify {
+		assert!(Borrowers::<T>::try_get(borrower, order.token).is_err());
+	}
+
+	extend_rental {
+		let (lender_key, lender) = signer::<T>();
+		let borrower: T::AccountId = whitelisted_caller();
+		T::Currency::make_free_balance_be(&borrower, 1_000_000_000_000u64.saturated_into());
+		T::Currency::make_free_balance_be(&T::FeeDestination::get(), 1_000_000u64.saturated_into());
+		let token = T::TokenNFT::mint(lender.clone())?;
+		T::TokenNFT::transfer_custodian(lender.clone(), borrower.clone(), token.clone())?;
+		let due_date = T::Timestamp::now().as_secs() + 86_400;
+		let order = Order {
+			lender: account_to_bytes(&lender)?,
+			borrower: account_to_bytes(&borrower)?,
+			fee: 1_000_000,
+			token: token.clone(),
+			due_date,
+			billing: BillingPlan::Upfront,
+			asset_id: None,
+			min_reputation: 0,
+			credential: None,
+			registry: Registry::NftCurrency,
+		};
+		Borrowers::<T>::insert(borrower.clone(), token.clone(), order.clone());
+		Renting::<T>::schedule_rental(&borrower, &order)?;
+		let message = order_message::<T>(&lender, None, &token, due_date + 86_400);
+		let signature = sp_io::crypto::sr25519_sign(KEY_TYPE, &lender_key, &message)
+			.unwrap()
+			.0
+			.to_vec();
+	}: _(RawOrigin::Signed(borrower.clone()), token.clone(), message, signature)
+	verify {
+		assert_eq!(Borrowers::<T>::get(borrower, token).due_date, due_date + 86_400);
+	}
+
+	return_asset {
+		// the scheduler dispatches the return of a periodic rental that still has an instalment
+		// pending, after blocks came slower than expected so the due date is already over
+		let billing = BillingPlan::EveryBlocks { period: 1, first: FirstPayment::Upfront };
+		let (borrower, order) = rented_order::<T>(billing);
+		let due_date = T::Timestamp::now().as_secs() + 86_400;
+		Renting::<T>::schedule_rental(&borrower, &Order { due_date, ..order.clone() })?;
+	}: _(RawOrigin::Root, borrower.clone(), order.token.clone())
+	verify {
+		assert!(Borrowers::<T>::try_get(borrower, order.token).is_err());
+	}
+
+	collect_repayment {
+		// the scheduler dispatches an instalment of a periodic rental, the instalment it dispatches
+		// is no longer known to it and the call schedules the next one
+		let billing = BillingPlan::EveryBlocks { period: 1, first: FirstPayment::Upfront };
+		let (borrower, order) = rented_order::<T>(billing);
+		let due_date = T::Timestamp::now().as_secs() + 86_400;
+		Renting::<T>::schedule_rental(&borrower, &Order { due_date, ..order.clone() })?;
+		T::Scheduler::cancel_named(Renting::<T>::task_id(
+			RentalTask::Repayment,
+			&borrower,
+			&order.token,
+		))?;
+		let next = frame_system::Pallet::<T>::current_block_number() + 1u32.into();
+		frame_system::Pallet::<T>::set_block_number(next);
+	}: _(RawOrigin::Root, borrower.clone(), order.token.clone())
+	verify {
+		assert!(Overdue::<T>::get(&borrower, &order.token).is_none());
+		let task = Renting::<T>::task_id(RentalTask::Repayment, &borrower, &order.token);
+		assert!(T::Scheduler::next_dispatch_time(task).is_ok());
+	}
+
+	retry_payment {
+		let billing = BillingPlan::EveryBlocks { period: 1, first: FirstPayment::Upfront };
+		let (borrower, order) = rented_order::<T>(billing);
+		let overdue = OverduePayment { amount: order.fee, late_fee: 0, attempts: 1 };
+		Overdue::<T>::insert(borrower.clone(), order.token.clone(), overdue);
+	}: _(RawOrigin::Root, borrower.clone(), order.token.clone())
+	verify {
+		assert!(Overdue::<T>::get(borrower, order.token).is_none());
+	}
+
+	set_marketplace_fee {
+		let fee = Permill::from_percent(5);
+		let origin = T::AdminOrigin::successful_origin();
+	}: _<T::RuntimeOrigin>(origin, fee)
+	verify {
+		assert_eq!(MarketplaceFee::<T>::get(), fee);
+	}
+
+	open_dispute {
+		let (borrower, order) = rented_order::<T>(BillingPlan::Upfront);
+	}: _(
+		RawOrigin::Signed(borrower.clone()),
+		Registry::NftCurrency,
+		order.token.clone(),
+		[7u8; 32]
+	)
+	verify {
+		assert!(Disputes::<T>::contains_key(borrower, order.token));
+	}
+
+	resolve_dispute {
+		let billing = BillingPlan::EveryBlocks { period: 1, first: FirstPayment::Upfront };
+		let (borrower, order) = rented_order::<T>(billing);
+		let due_date = T::Timestamp::now().as_secs() + 86_400;
+		Renting::<T>::schedule_rental(&borrower, &Order { due_date, ..order.clone() })?;
+		Renting::<T>::open_dispute(
+			RawOrigin::Signed(borrower.clone()).into(),
+			Registry::NftCurrency,
+			order.token.clone(),
+			[7u8; 32],
+		)?;
+		Renting::<T>::collect_repayment(RawOrigin::Root.into(), borrower.clone(), order.token.clone())?;
+		let resolution = DisputeResolution {
+			in_favour_of: DisputeParty::Lender,
+			lender_share: Permill::from_percent(50),
+			action: DisputeAction::Extend { due_date: due_date + 86_400 },
+		};
+		let origin = T::ArbiterOrigin::successful_origin();
+	}: _<T::RuntimeOrigin>(origin, borrower.clone(), order.token.clone(), resolution)
+	verify {
+		assert!(Rulings::<T>::contains_key(&borrower, &order.token));
+		assert!(!Disputes::<T>::contains_key(borrower, order.token));
+	}
+
+	add_to_allowlist {
+		let lender: T::AccountId = whitelisted_caller();
+		let borrower: T::AccountId = account("borrower", 0, 0);
+	}: _(RawOrigin::Signed(lender.clone()), borrower.clone())
+	verify {
+		assert!(Allowlists::<T>::contains_key(lender, borrower));
+	}
+
+	remove_from_allowlist {
+		let lender: T::AccountId = whitelisted_caller();
+		let borrower: T::AccountId = account("borrower", 0, 0);
+		Allowlists::<T>::insert(lender.clone(), borrower.clone(), ());
+	}: _(RawOrigin::Signed(lender.clone()), borrower.clone())
+	verify {
+		assert!(!Allowlists::<T>::contains_key(lender, borrower));
+	}
+
+	cleanup {
+		let n in 1 .. T::MaxCleanup::get();
+		// rentals past due without a return cost more than cancelled orders
+		let entries = (0..n)
+			.map(|_| {
+				let (borrower, order) = rented_order::<T>(BillingPlan::Upfront);
+				StaleEntry::ExpiredRental(borrower, order.token)
+			})
+			.collect::<Vec<_>>();
+	}: _(RawOrigin::None, entries)
+	verify {
+		assert_eq!(Borrowers::<T>::iter().count(), 0);
+	}
+
+	impl_benchmark_test_suite!(Renting, crate::mock::new_test_ext(), crate::mock::Test);
+}
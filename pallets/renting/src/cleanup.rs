@@ -0,0 +1,60 @@
+//! Storage left behind by rentals, found by the off-chain worker and removed with `cleanup`.
+//!
+//! Rentals schedule their own return, so storage only goes stale when something slips through:
+//! a cancelled order can no longer be matched once its due date is over, and a rental whose due
+//! date is over but whose return is no longer scheduled would otherwise never end.
+
+use crate::{Borrowers, CancelOrder, Config, Disputes, Pallet, RentalTask};
+use frame_support::{
+	pallet_prelude::*,
+	traits::{schedule::v3::Named as ScheduleNamed, UnixTime},
+};
+use sp_std::vec::Vec;
+
+/// Entry `cleanup` removes, valid only as long as it is stale.
+#[derive(Clone, Encode, Decode, PartialEq, Eq, TypeInfo, Debug)]
+pub enum StaleEntry<AccountId> {
+	/// Key of a cancelled order whose due date is over.
+	CancelledOrder(Vec<u8>),
+	/// Borrower and token of a rental past its due date with no return scheduled.
+	ExpiredRental(AccountId, Vec<u8>),
+}
+
+impl<T: Config> Pallet<T> {
+	/// Whether `entry` can be removed right now.
+	pub(crate) fn is_stale(entry: &StaleEntry<T::AccountId>) -> bool {
+		let now = T::Timestamp::now().as_secs();
+		match entry {
+			StaleEntry::CancelledOrder(key) => {
+				CancelOrder::<T>::get(key).map_or(false, |order| order.due_date <= now)
+			},
+			StaleEntry::ExpiredRental(borrower, token_id) => {
+				let order = match Borrowers::<T>::try_get(borrower, token_id) {
+					Ok(order) => order,
+					Err(_) => return false,
+				};
+				// a disputed rental waits for the arbiter, not for its return
+				order.due_date <= now
+					&& !Disputes::<T>::contains_key(borrower, token_id)
+					&& T::Scheduler::next_dispatch_time(Self::task_id(
+						RentalTask::Return,
+						borrower,
+						token_id,
+					))
+					.is_err()
+			},
+		}
+	}
+
+	/// Up to `limit` stale entries, cancelled orders first.
+	pub(crate) fn stale_entries(limit: usize) -> Vec<StaleEntry<T::AccountId>> {
+		let orders = CancelOrder::<T>::iter_keys().map(StaleEntry::CancelledOrder);
+		let rentals = Borrowers::<T>::iter_keys()
+			.map(|(borrower, token_id)| StaleEntry::ExpiredRental(borrower, token_id));
+		orders
+			.chain(rentals)
+			.filter(|entry| Self::is_stale(entry))
+			.take(limit)
+			.collect()
+	}
+}
@@ -18,22 +18,23 @@ where
 	Ok(bytes)
 }
 
-pub fn hex_string_to_vec(str: String) -> Vec<u8> {
+// Decode a hex string, `None` if it contains anything but hex digits.
+pub fn hex_string_to_vec(str: String) -> Option<Vec<u8>> {
 	let hex_string = str.replace("0x", "");
 	let split_string = hex_string
 		.as_bytes()
 		.chunks(2)
 		.map(str::from_utf8)
 		.collect::<Result<Vec<&str>, _>>()
-		.unwrap();
+		.ok()?;
 	let mut bytes: Vec<u8> = Vec::new();
 	for part in split_string.into_iter() {
-		bytes.push(hex_to_deci(part));
+		bytes.push(hex_to_deci(part)?);
 	}
-	bytes
+	Some(bytes)
 }
 
-fn hex_to_deci(str: &str) -> u8 {
+fn hex_to_deci(str: &str) -> Option<u8> {
 	let mut deci: u8 = 0;
 	let mut i: u32 = 0;
 	let hex_vec: Vec<char> = str.trim_end().chars().rev().collect();
@@ -59,13 +60,13 @@ fn hex_to_deci(str: &str) -> u8 {
 			_ => 16,
 		};
 		if temp == 16 {
-			return u8::MAX;
+			return None;
 		}
-		deci += temp * u8::pow(16, i);
+		deci = deci.checked_add(temp.checked_mul(16u8.checked_pow(i)?)?)?;
 		i += 1;
 	}
 
-	return deci;
+	return Some(deci);
 }
 
 // pub fn convert_bytes_to_hex(bytes: [u8;32])-> String{
@@ -75,28 +76,28 @@ fn hex_to_deci(str: &str) -> u8 {
 // 	res
 // }
 
-pub fn convert_bytes_to_accountid<AccountId>(bytes: [u8; 32]) -> AccountId
+// Decode an AccountId from its 32 bytes, `None` if the runtime AccountId is not 32 bytes long.
+pub fn convert_bytes_to_accountid<AccountId>(bytes: [u8; 32]) -> Option<AccountId>
 where
 	AccountId: Encode + ?Sized + Decode,
 {
 	let account32: AccountId32 = bytes.into();
 	let mut to32: &[u8] = AccountId32::as_ref(&account32);
-	let to_address = AccountId::decode(&mut to32).unwrap();
-	to_address
+	AccountId::decode(&mut to32).ok()
 }
 
-pub fn convert_string_to_accountid<AccountId>(account_str: &str) -> AccountId
+// Decode an AccountId from its SS58 string, `None` if the string is not valid base58.
+pub fn convert_string_to_accountid<AccountId>(account_str: &str) -> Option<AccountId>
 where
 	AccountId: Encode + ?Sized + Decode,
 {
 	let mut output = vec![0xFF; 35];
-	bs58::decode(account_str).into(&mut output).unwrap();
+	bs58::decode(account_str).into(&mut output).ok()?;
 	let cut_address_vec: Vec<u8> = output.drain(1..33).collect();
 	let mut array = [0; 32];
 	let bytes = &cut_address_vec[..array.len()];
 	array.copy_from_slice(bytes);
 	let account32: AccountId32 = array.into();
 	let mut to32 = AccountId32::as_ref(&account32);
-	let to_address = AccountId::decode(&mut to32).unwrap();
-	to_address
+	AccountId::decode(&mut to32).ok()
 }
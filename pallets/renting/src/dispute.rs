@@ -0,0 +1,60 @@
+use frame_support::pallet_prelude::*;
+use sp_runtime::Permill;
+
+/// A side of a rental.
+#[derive(Clone, Copy, Encode, Decode, PartialEq, Eq, TypeInfo, Debug)]
+pub enum DisputeParty {
+	Lender,
+	Borrower,
+}
+
+impl DisputeParty {
+	pub fn other(&self) -> Self {
+		match self {
+			DisputeParty::Lender => DisputeParty::Borrower,
+			DisputeParty::Borrower => DisputeParty::Lender,
+		}
+	}
+}
+
+/// What happens to a disputed rental once the arbiter ruled.
+#[derive(Clone, Copy, Encode, Decode, PartialEq, Eq, TypeInfo, Debug)]
+pub enum DisputeAction {
+	/// The rental goes on until its due date.
+	Continue,
+	/// The rental goes on until `due_date`, a later Unix time in seconds.
+	Extend { due_date: u64 },
+	/// The asset goes back to the lender now.
+	Terminate,
+}
+
+/// Ruling of the arbiter on a dispute.
+#[derive(Clone, Copy, Encode, Decode, PartialEq, Eq, TypeInfo, Debug)]
+pub struct DisputeResolution {
+	/// Party the dispute is settled for, it gets the deposit of the dispute back or is paid it.
+	pub in_favour_of: DisputeParty,
+	/// Share of the escrow paid to the lender, the rest goes back to the borrower.
+	pub lender_share: Permill,
+	pub action: DisputeAction,
+}
+
+/// An open dispute. While it is open the asset is not returned and the rent owed to the lender is
+/// held in escrow.
+#[derive(Clone, Encode, Decode, PartialEq, TypeInfo, Debug)]
+pub struct Dispute<BlockNumber> {
+	pub(crate) opened_by: DisputeParty,
+	pub(crate) evidence_hash: [u8; 32],
+	pub(crate) opened_at: BlockNumber,
+	pub(crate) deposit: u64, // native currency, paid by the party opening the dispute
+	pub(crate) escrow: u64,  // rent paid while the dispute is open, in the currency of the order
+}
+
+/// Outcome of the last dispute of a rental.
+#[derive(Clone, Encode, Decode, PartialEq, TypeInfo, Debug)]
+pub struct Ruling<BlockNumber> {
+	pub(crate) opened_by: DisputeParty,
+	pub(crate) evidence_hash: [u8; 32],
+	pub(crate) resolution: DisputeResolution,
+	pub(crate) escrow: u64,
+	pub(crate) resolved_at: BlockNumber,
+}
@@ -1,44 +1,80 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
-use frame_support::traits::{ExistenceRequirement, UnixTime};
+use frame_support::traits::{
+	fungibles,
+	schedule::{
+		v3::Named as ScheduleNamed, DispatchTime, Priority, HARD_DEADLINE, LOWEST_PRIORITY,
+	},
+	EnsureOrigin, ExistenceRequirement, QueryPreimage, StorePreimage, UnixTime,
+};
 use frame_support::{
 	dispatch::{result::Result, DispatchError, DispatchResult},
 	ensure, log,
 	pallet_prelude::*,
 	traits::{Currency, Randomness},
+	transactional, PalletId,
+};
+use frame_system::{
+	ensure_none, ensure_root, ensure_signed,
+	offchain::{SendTransactionTypes, SubmitTransaction},
+	pallet_prelude::*,
 };
-use frame_system::{ensure_signed, pallet_prelude::*};
-use lite_json::json_parser::parse_json;
+use lite_json::{json::JsonValue, json_parser::parse_json};
 use scale_info::prelude::string::String;
 use sp_core::sr25519;
-use sp_runtime::traits::BlockNumberProvider;
+use sp_runtime::traits::{BlockNumberProvider, Dispatchable, One, Saturating, Zero};
 use sp_runtime::{
-	traits::{IdentifyAccount, Verify},
-	AnySignature, SaturatedConversion,
+	traits::{AccountIdConversion, IdentifyAccount, Verify},
+	AnySignature, Permill, SaturatedConversion,
 };
 pub use sp_std::vec;
 pub use sp_std::vec::Vec;
 pub use sp_std::{convert::Into, str};
 
+pub use cleanup::StaleEntry;
 use convert::*;
-pub use order::Order;
+pub use dispute::{Dispute, DisputeAction, DisputeParty, DisputeResolution, Ruling};
+pub use order::{BillingPlan, Credential, FirstPayment, Order, OverduePayment, Registry};
 pub use pallet::*;
 use pallet_nft_currency::NonFungibleToken;
+pub use reputation::Reputation;
+pub use uniques::UniquesItems;
+pub use usage::{UsageRight, UsageRightTokens};
+pub use weights::WeightInfo;
+mod cleanup;
 mod convert;
+mod dispute;
+pub mod migration;
 mod order;
+mod reputation;
+mod uniques;
+mod usage;
+pub mod weights;
 
 #[cfg(feature = "runtime-benchmarks")]
 mod benchmarking;
 
-/// An index to a block.
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+pub type CallOf<T> = <T as Config>::RuntimeCall;
 
-// Time is measured by number of blocks.
-pub const MILLISECS_PER_BLOCK: u32 = 6000;
-pub const MINUTES: u32 = 60000 / (MILLISECS_PER_BLOCK);
-pub const HOURS: u32 = MINUTES * 60;
-pub const DAYS: u32 = HOURS * 24;
-pub const WEEKS: u32 = DAYS * 7;
-pub const MONTHS: u32 = WEEKS * 4;
+// Instalments are collected before the asset is returned when both fall due in the same block.
+const PAYMENT_PRIORITY: Priority = HARD_DEADLINE;
+const RETURN_PRIORITY: Priority = LOWEST_PRIORITY;
+// Blocks after the one a task is due in tried when the agenda of that block is full.
+const MAX_SCHEDULE_DELAY: u32 = 10;
+
+/// The named scheduler tasks every rental may have pending.
+#[derive(Clone, Copy, Encode)]
+enum RentalTask {
+	Return,
+	Repayment,
+	Retry,
+}
 
 #[frame_support::pallet]
 pub mod pallet {
@@ -46,18 +82,75 @@ pub mod pallet {
 
 	/// Configure the pallet by specifying the parameters and types on which it depends.
 	#[pallet::config]
-	pub trait Config: frame_system::Config {
+	pub trait Config: frame_system::Config + SendTransactionTypes<Call<Self>> {
 		type Currency: Currency<Self::AccountId>;
+		/// Fungible assets an order may be priced in instead of the native currency.
+		type Assets: fungibles::Transfer<Self::AccountId, AssetId = u32>;
 		type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
+		/// The overarching call type, so a rental can schedule its own return and repayments.
+		type RuntimeCall: Parameter
+			+ Dispatchable<RuntimeOrigin = Self::RuntimeOrigin>
+			+ From<Call<Self>>;
+		/// Origin the scheduled rental calls are dispatched with.
+		type PalletsOrigin: From<frame_system::RawOrigin<Self::AccountId>>;
+		/// Runs the named return and repayment tasks of every rental.
+		type Scheduler: ScheduleNamed<Self::BlockNumber, CallOf<Self>, Self::PalletsOrigin>;
+		/// Stores the scheduled calls too large to be inlined.
+		type Preimages: QueryPreimage + StorePreimage;
 		type Timestamp: UnixTime;
+		/// Expected time between two blocks in milliseconds, used to turn due dates into blocks.
+		#[pallet::constant]
+		type BlockTime: Get<u64>;
 		type Randomness: Randomness<Self::Hash, Self::BlockNumber>;
 		type TokenNFT: NonFungibleToken<Self::AccountId>;
+		/// Tokens of orders with `"registry":"uniques"`, usually `UniquesItems<Self>`.
+		type Uniques: NonFungibleToken<Self::AccountId>;
 		type Signature: Verify<Signer = Self::PublicKey> + Encode + Decode + Parameter;
 		type PublicKey: IdentifyAccount<AccountId = Self::PublicKey> + Encode + Decode + Parameter;
+		/// Account receiving the marketplace cut of every rental payment. A cut too small to
+		/// create the account with is left to the lender.
+		type FeeDestination: Get<Self::AccountId>;
+		/// Marketplace cut applied until the admin origin sets a different rate.
+		type DefaultMarketplaceFee: Get<Permill>;
+		/// Blocks a borrower has to settle a missed instalment before the asset is taken back.
+		#[pallet::constant]
+		type GracePeriod: Get<Self::BlockNumber>;
+		/// How many times a missed instalment is retried within the grace period.
+		#[pallet::constant]
+		type MaxPaymentRetries: Get<u32>;
+		/// Late fee, relative to the instalment, added on every failed attempt.
+		#[pallet::constant]
+		type LateFee: Get<Permill>;
+		/// Origin setting the marketplace fee.
+		type AdminOrigin: EnsureOrigin<Self::RuntimeOrigin>;
+		/// Origin ruling on disputes between lenders and borrowers.
+		type ArbiterOrigin: EnsureOrigin<Self::RuntimeOrigin>;
+		/// Deposit in the native currency paid to open a dispute, it goes to the party the
+		/// dispute is settled for.
+		#[pallet::constant]
+		type DisputeDeposit: Get<u64>;
+		/// Escrow accounts of disputes are derived from this id.
+		#[pallet::constant]
+		type PalletId: Get<PalletId>;
+		/// Blocks between two scans of the off-chain worker for stale entries.
+		#[pallet::constant]
+		type CleanupInterval: Get<Self::BlockNumber>;
+		/// Most entries a single `cleanup` removes.
+		#[pallet::constant]
+		type MaxCleanup: Get<u32>;
+		/// Priority of the unsigned `cleanup` transactions.
+		#[pallet::constant]
+		type UnsignedPriority: Get<TransactionPriority>;
+		/// Weight information for extrinsics in this pallet.
+		type WeightInfo: WeightInfo;
 	}
 
+	/// Version of the storage layout, see `migration`.
+	const STORAGE_VERSION: StorageVersion = StorageVersion::new(1);
+
 	#[pallet::pallet]
 	#[pallet::generate_store(pub (super) trait Store)]
+	#[pallet::storage_version(STORAGE_VERSION)]
 	#[pallet::without_storage_info]
 	pub struct Pallet<T>(_);
 
@@ -83,16 +176,80 @@ pub mod pallet {
 		StorageMap<_, Blake2_128Concat, Vec<u8>, Order, OptionQuery>;
 
 	#[pallet::storage]
-	#[pallet::getter(fn due_block)]
-	// Record the block stop the rental
-	pub(super) type DueBlock<T: Config> =
-		StorageMap<_, Blake2_128Concat, T::BlockNumber, Vec<Order>, ValueQuery>;
+	#[pallet::getter(fn overdue)]
+	// AccountId, token Id => missed payment still in its grace period
+	pub(super) type Overdue<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		T::AccountId,
+		Blake2_128Concat,
+		Vec<u8>,
+		OverduePayment,
+		OptionQuery,
+	>;
 
 	#[pallet::storage]
-	#[pallet::getter(fn repayment)]
-	// Record the block to pay for the rental
-	pub(super) type Repayment<T: Config> =
-		StorageMap<_, Blake2_128Concat, T::BlockNumber, Vec<Order>, ValueQuery>;
+	#[pallet::getter(fn marketplace_fee)]
+	// Share of every rental payment kept by the marketplace
+	pub(super) type MarketplaceFee<T: Config> =
+		StorageValue<_, Permill, ValueQuery, T::DefaultMarketplaceFee>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn disputes)]
+	// AccountId, token Id => dispute waiting for the arbiter
+	pub(super) type Disputes<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		T::AccountId,
+		Blake2_128Concat,
+		Vec<u8>,
+		Dispute<T::BlockNumber>,
+		OptionQuery,
+	>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn rulings)]
+	// AccountId, token Id => outcome of the last dispute of the rental
+	pub(super) type Rulings<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		T::AccountId,
+		Blake2_128Concat,
+		Vec<u8>,
+		Ruling<T::BlockNumber>,
+		OptionQuery,
+	>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn item_custodians)]
+	// encoded (collection, item) => borrower of a rented `pallet_uniques` item
+	pub(super) type ItemCustodians<T: Config> =
+		StorageMap<_, Blake2_128Concat, Vec<u8>, T::AccountId, OptionQuery>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn usage_rights)]
+	// token Id => borrower allowed to use the rented token, until when
+	pub(super) type UsageRights<T: Config> =
+		StorageMap<_, Blake2_128Concat, Vec<u8>, UsageRight<T::AccountId>, OptionQuery>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn reputation)]
+	// AccountId => track record as lender and borrower
+	pub(super) type Reputations<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::AccountId, Reputation, ValueQuery>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn allowlists)]
+	// lender, borrower => the lender allows the borrower to rent its tokens
+	pub(super) type Allowlists<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		T::AccountId,
+		Blake2_128Concat,
+		T::AccountId,
+		(),
+		OptionQuery,
+	>;
 
 	// Pallets use events to inform users when important changes are made.
 	// https://docs.substrate.io/v3/runtime/events-and-errors
@@ -104,6 +261,32 @@ pub mod pallet {
 		StopRenting(Vec<u8>, T::AccountId),
 		ReturnAsset(T::AccountId, T::AccountId, Vec<u8>),
 		RepaymentRental(T::AccountId, T::AccountId, Vec<u8>),
+		/// The marketplace took its cut of a rental payment. [borrower, token, amount]
+		MarketplaceFeeTaken(T::AccountId, Vec<u8>, u64),
+		/// The admin origin changed the marketplace fee rate. [rate]
+		MarketplaceFeeSet(Permill),
+		/// An instalment could not be paid and will be retried.
+		/// [borrower, lender, token, attempts, late_fee]
+		PaymentOverdue(T::AccountId, T::AccountId, Vec<u8>, u32, u64),
+		/// The lender agreed to a later due date. [borrower, lender, token, due_date]
+		ExtendRental(T::AccountId, T::AccountId, Vec<u8>, u64),
+		/// A party disputed a rental, which is frozen until the arbiter rules.
+		/// [borrower, token, opened_by, evidence_hash]
+		DisputeOpened(T::AccountId, Vec<u8>, T::AccountId, [u8; 32]),
+		/// The arbiter ruled on a dispute. [borrower, token, resolution]
+		DisputeResolved(T::AccountId, Vec<u8>, DisputeResolution),
+		/// A lender added a borrower to its allowlist. [lender, borrower]
+		AllowlistAdded(T::AccountId, T::AccountId),
+		/// A lender removed a borrower from its allowlist. [lender, borrower]
+		AllowlistRemoved(T::AccountId, T::AccountId),
+		/// The usage right of a rented token changed hands, it is burned when there is no user.
+		/// [token, user, expires]
+		UpdateUser(Vec<u8>, Option<T::AccountId>, u64),
+		/// Stale entries were removed. [entries]
+		CleanedUp(u32),
+		/// The token of a rental was burned, the rental ended without a return.
+		/// [borrower, lender, token]
+		RentalTokenBurned(T::AccountId, T::AccountId, Vec<u8>),
 	}
 
 	// Errors inform users that something went wrong.
@@ -121,74 +304,56 @@ pub mod pallet {
 		NotCaller,
 		AlreadyCanceled,
 		NotOwnerOfOrder,
+		/// The token is already rented out, or the borrower lacks the credential of the order.
 		NotQualified,
 		NotPaidType,
 		TimeNotLongEnough,
 		CannotTransferCustodian,
+		CannotPayFee,
+		NotMatchAsset,
+		NotMatchBillingPlan,
+		/// The order message is not a JSON object with the expected fields.
+		InvalidOrder,
+		/// An account in the order cannot be decoded.
+		InvalidAccount,
+		/// The token of the order is not a hex string.
+		InvalidToken,
+		/// Only the lender and the borrower of a rental can dispute it.
+		NotParty,
+		/// The rental is already disputed.
+		AlreadyDisputed,
+		/// The rental is frozen by an open dispute.
+		Disputed,
+		/// The rental is not disputed.
+		NoDispute,
+		/// The due date of the rental is not over yet.
+		NotDue,
+		/// The reputation of the borrower is below the minimum of the order.
+		ReputationTooLow,
+		/// The order names a credential that is not known.
+		InvalidCredential,
+		/// The order names a registry that is not known.
+		InvalidRegistry,
+		/// An instalment of the rental is overdue, it has to be settled first.
+		PaymentOverdue,
 	}
 
 	#[pallet::hooks]
 	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
-		fn on_finalize(_n: BlockNumberFor<T>) {
-			if DueBlock::<T>::contains_key(_n) {
-				for order in Self::due_block(_n).into_iter() {
-					let lender: T::AccountId = convert_bytes_to_accountid(order.lender);
-					let borrower: T::AccountId = convert_bytes_to_accountid(order.borrower);
-					// transfer asset back to lender
-					T::TokenNFT::transfer_custodian(
-						borrower.clone(),
-						lender.clone(),
-						order.token.clone(),
-					)
-					.expect("Cannot transfer custodian");
-
-					Borrowers::<T>::remove(borrower.clone(), order.token.clone());
-
-					Self::deposit_event(Event::ReturnAsset(borrower, lender, order.token));
-				}
-				DueBlock::<T>::remove(_n);
+		/// Every `CleanupInterval` blocks, submit the stale entries found as an unsigned
+		/// `cleanup`. The transaction pool checks them again before the call is included.
+		fn offchain_worker(now: T::BlockNumber) {
+			if !(now % T::CleanupInterval::get().max(One::one())).is_zero() {
+				return;
 			}
-		}
-
-		fn on_initialize(_n: BlockNumberFor<T>) -> Weight {
-			if Repayment::<T>::contains_key(_n) {
-				for order in Self::repayment(_n).into_iter() {
-					let lender: T::AccountId = convert_bytes_to_accountid(order.lender);
-					let borrower: T::AccountId = convert_bytes_to_accountid(order.borrower);
-					if Borrowers::<T>::try_get(borrower.clone(), order.token.clone()).is_err() {
-						continue;
-					}
-					if T::Currency::transfer(
-						&borrower,
-						&lender,
-						order.fee.saturated_into(),
-						ExistenceRequirement::KeepAlive,
-					)
-					.is_err()
-					{
-						T::TokenNFT::transfer_custodian(
-							borrower.clone(),
-							lender.clone(),
-							order.token.clone(),
-						)
-						.expect("Cannot transfer custodian");
-						Self::deposit_event(Event::ReturnAsset(
-							borrower.clone(),
-							lender.clone(),
-							order.token.clone(),
-						));
-						Borrowers::<T>::remove(borrower.clone(), order.token.clone());
-					} else {
-						Self::deposit_event(Event::RepaymentRental(
-							borrower.clone(),
-							lender.clone(),
-							order.token.clone(),
-						));
-					}
-				}
-				Repayment::<T>::remove(_n);
+			let entries = Self::stale_entries(T::MaxCleanup::get() as usize);
+			if entries.is_empty() {
+				return;
+			}
+			let call = Call::cleanup { entries };
+			if SubmitTransaction::<T, Call<T>>::submit_unsigned_transaction(call.into()).is_err() {
+				log::error!("failed to submit the cleanup of stale rental entries");
 			}
-			T::DbWeight::get().reads_writes(16, 15)
 		}
 	}
 
@@ -197,7 +362,7 @@ pub mod pallet {
 	// Dispatchable functions must be annotated with a weight and must return a DispatchResult.
 	#[pallet::call]
 	impl<T: Config> Pallet<T> {
-		#[pallet::weight(35_678_000)]
+		#[pallet::weight(T::WeightInfo::create_rental())]
 		pub fn create_rental(
 			origin: OriginFor<T>,
 			lender: T::AccountId,
@@ -226,7 +391,8 @@ pub mod pallet {
 					&& !CancelOrder::<T>::contains_key(order_right.clone().encode()),
 				Error::<T>::AlreadyCanceled
 			);
-			let fulfilled_order = Self::match_order(lender.clone(), order_left, order_right)?;
+			let fulfilled_order =
+				Self::match_order(lender.clone(), &borrower, order_left, order_right)?;
 
 			let token_id = fulfilled_order.token.clone();
 
@@ -234,17 +400,15 @@ pub mod pallet {
 				*order_detail = fulfilled_order.clone();
 			});
 
-			let due_block = Self::get_due_block(fulfilled_order.clone());
-			DueBlock::<T>::mutate(due_block.clone(), |orders| {
-				orders.push(fulfilled_order.clone());
-			});
+			Self::schedule_rental(&borrower, &fulfilled_order)?;
 
 			Self::transfer_custodian(&lender, &borrower, fulfilled_order.clone())?;
+			Self::grant_usage(&token_id, &borrower, fulfilled_order.due_date);
 			Self::deposit_event(Event::MatchOrder(lender, borrower, token_id));
 			Ok(())
 		}
 
-		#[pallet::weight(35_678_000)]
+		#[pallet::weight(T::WeightInfo::cancel_offer())]
 		pub fn cancel_offer(
 			origin: OriginFor<T>,
 			message: Vec<u8>,
@@ -268,33 +432,342 @@ pub mod pallet {
 		}
 
 		/// Borrower stop renting NFT, the fee cannot refund
-		#[pallet::weight(35_678_000)]
+		#[pallet::weight(T::WeightInfo::stop_renting())]
 		pub fn stop_renting(origin: OriginFor<T>, token_id: Vec<u8>) -> DispatchResult {
 			let caller = ensure_signed(origin)?;
-			let order = Self::borrowers(caller.clone(), token_id.clone());
-			let lender: T::AccountId = convert_bytes_to_accountid(order.lender);
-			let borrower: T::AccountId = convert_bytes_to_accountid(order.borrower);
+			let order = Borrowers::<T>::try_get(caller.clone(), token_id.clone())
+				.map_err(|_| Error::<T>::NoneExist)?;
+			let lender = Self::account_of(order.lender)?;
+			let borrower = Self::account_of(order.borrower)?;
 
 			// check the order to return token
 			ensure!(caller == borrower.clone(), Error::<T>::NotMatchBorrower);
+			ensure!(!Disputes::<T>::contains_key(&borrower, &token_id), Error::<T>::Disputed);
+			ensure!(!Overdue::<T>::contains_key(&borrower, &token_id), Error::<T>::PaymentOverdue);
+			if !Self::token_exists(order.registry, &order.token) {
+				return Self::end_burned_rental(&borrower, &lender, &order);
+			}
 			ensure!(
-				caller == T::TokenNFT::owner_of_token(order.token.clone()),
+				Some(caller.clone()) == Self::custodian(order.registry, &order.token),
 				Error::<T>::NotOwner
 			);
 
-			// transfer to the lender
-			T::TokenNFT::transfer_custodian(borrower.clone(), lender, order.token.clone())
-				.expect("Cannot transfer custodian");
-			Borrowers::<T>::remove(borrower.clone(), order.token.clone());
-			// update storage
+			// transfer to the lender and cancel the pending return and repayments
+			Self::end_rental(&borrower, &lender, &order)?;
+			Reputations::<T>::mutate(&borrower, |r| r.early_stops.saturating_inc());
 			Self::deposit_event(Event::StopRenting(token_id, caller));
 			Ok(())
 		}
+
+		/// Borrower move the due date of a rental with an order signed by the lender
+		#[pallet::weight(T::WeightInfo::extend_rental())]
+		pub fn extend_rental(
+			origin: OriginFor<T>,
+			token_id: Vec<u8>,
+			message: Vec<u8>,
+			signature: Vec<u8>,
+		) -> DispatchResult {
+			let borrower = ensure_signed(origin)?;
+			let order = Borrowers::<T>::try_get(borrower.clone(), token_id.clone())
+				.map_err(|_| Error::<T>::NoneExist)?;
+			ensure!(!Disputes::<T>::contains_key(&borrower, &token_id), Error::<T>::Disputed);
+			let lender = Self::account_of(order.lender)?;
+			Self::verify_signature(message.clone(), signature, &lender)?;
+
+			let extension = Self::parse_to_order(order.lender, [0u8; 32], &message)?;
+			ensure!(
+				!CancelOrder::<T>::contains_key(extension.encode()),
+				Error::<T>::AlreadyCanceled
+			);
+			ensure!(extension.token == order.token, Error::<T>::NotMatchToken);
+			ensure!(extension.due_date > order.due_date, Error::<T>::TimeNotLongEnough);
+			ensure!(extension.asset_id == order.asset_id, Error::<T>::NotMatchAsset);
+			ensure!(extension.billing == order.billing, Error::<T>::NotMatchBillingPlan);
+
+			// upfront rentals pay for the extension now, periodic ones keep paying every period
+			if extension.billing == BillingPlan::Upfront {
+				Self::pay_rental(&borrower, &lender, &extension, extension.fee)?;
+			}
+			let extended =
+				Order { fee: extension.fee, due_date: extension.due_date, ..order.clone() };
+			Borrowers::<T>::insert(borrower.clone(), token_id.clone(), extended.clone());
+			Self::grant_usage(&token_id, &borrower, extended.due_date);
+
+			let now = frame_system::Pallet::<T>::current_block_number();
+			let due_block = now.saturating_add(Self::blocks_until(extended.due_date));
+			T::Scheduler::reschedule_named(
+				Self::task_id(RentalTask::Return, &borrower, &token_id),
+				DispatchTime::At(due_block),
+			)?;
+			if T::Scheduler::next_dispatch_time(Self::task_id(
+				RentalTask::Repayment,
+				&borrower,
+				&token_id,
+			))
+			.is_err()
+			{
+				Self::schedule_next_repayment(now, &borrower, &extended)?;
+			}
+
+			Self::deposit_event(Event::ExtendRental(borrower, lender, token_id, extended.due_date));
+			Ok(())
+		}
+
+		/// Return a rented asset to its lender, dispatched by the scheduler at the due block.
+		///
+		/// The due block is estimated from `BlockTime`: a return that fires early is moved to the
+		/// due date, but when blocks are slower than expected it fires late by the missed slots.
+		/// Anyone can therefore end a rental with a signed call as soon as its due date is over,
+		/// or as soon as its token was burned.
+		#[pallet::weight(T::WeightInfo::return_asset())]
+		pub fn return_asset(
+			origin: OriginFor<T>,
+			borrower: T::AccountId,
+			token_id: Vec<u8>,
+		) -> DispatchResult {
+			if ensure_root(origin.clone()).is_err() {
+				ensure_signed(origin)?;
+				let order = Borrowers::<T>::try_get(&borrower, &token_id)
+					.map_err(|_| Error::<T>::NoneExist)?;
+				ensure!(
+					T::Timestamp::now().as_secs() >= order.due_date
+						|| !Self::token_exists(order.registry, &token_id),
+					Error::<T>::NotDue
+				);
+				ensure!(!Disputes::<T>::contains_key(&borrower, &token_id), Error::<T>::Disputed);
+				return Self::do_return_asset(borrower, token_id);
+			}
+			// a failing rental must not fail the scheduled call, it is logged and skipped
+			if let Err(e) = Self::do_return_asset(borrower, token_id.clone()) {
+				log::warn!("skip return of token {:?}: {:?}", token_id, e);
+			}
+			Ok(())
+		}
+
+		/// Collect a periodic instalment, dispatched by the scheduler every billing period
+		#[pallet::weight(T::WeightInfo::collect_repayment())]
+		pub fn collect_repayment(
+			origin: OriginFor<T>,
+			borrower: T::AccountId,
+			token_id: Vec<u8>,
+		) -> DispatchResult {
+			ensure_root(origin)?;
+			if let Err(e) = Self::do_collect_repayment(borrower, token_id.clone()) {
+				log::warn!("skip repayment of token {:?}: {:?}", token_id, e);
+			}
+			Ok(())
+		}
+
+		/// Retry a missed instalment, taking the asset back once the grace period is over
+		#[pallet::weight(T::WeightInfo::retry_payment())]
+		pub fn retry_payment(
+			origin: OriginFor<T>,
+			borrower: T::AccountId,
+			token_id: Vec<u8>,
+		) -> DispatchResult {
+			ensure_root(origin)?;
+			if let Err(e) = Self::do_retry_payment(borrower, token_id.clone()) {
+				log::warn!("skip payment retry of token {:?}: {:?}", token_id, e);
+			}
+			Ok(())
+		}
+
+		/// Set the share of every rental payment kept by the marketplace
+		#[pallet::weight(T::WeightInfo::set_marketplace_fee())]
+		pub fn set_marketplace_fee(origin: OriginFor<T>, fee: Permill) -> DispatchResult {
+			T::AdminOrigin::ensure_origin(origin)?;
+			MarketplaceFee::<T>::put(fee);
+			Self::deposit_event(Event::MarketplaceFeeSet(fee));
+			Ok(())
+		}
+
+		/// Allow `borrower` to match orders of the caller requiring the allowlist credential.
+		#[pallet::weight(T::WeightInfo::add_to_allowlist())]
+		pub fn add_to_allowlist(origin: OriginFor<T>, borrower: T::AccountId) -> DispatchResult {
+			let lender = ensure_signed(origin)?;
+			Allowlists::<T>::insert(lender.clone(), borrower.clone(), ());
+			Self::deposit_event(Event::AllowlistAdded(lender, borrower));
+			Ok(())
+		}
+
+		/// Take `borrower` off the allowlist of the caller, ongoing rentals are not affected.
+		#[pallet::weight(T::WeightInfo::remove_from_allowlist())]
+		pub fn remove_from_allowlist(
+			origin: OriginFor<T>,
+			borrower: T::AccountId,
+		) -> DispatchResult {
+			let lender = ensure_signed(origin)?;
+			ensure!(Allowlists::<T>::contains_key(&lender, &borrower), Error::<T>::NoneExist);
+			Allowlists::<T>::remove(lender.clone(), borrower.clone());
+			Self::deposit_event(Event::AllowlistRemoved(lender, borrower));
+			Ok(())
+		}
+
+		/// Lender or borrower dispute an ongoing rental. The asset stays with the borrower and the
+		/// rent owed to the lender is held in escrow until the arbiter rules.
+		#[pallet::weight(T::WeightInfo::open_dispute())]
+		pub fn open_dispute(
+			origin: OriginFor<T>,
+			registry: Registry,
+			token_id: Vec<u8>,
+			evidence_hash: [u8; 32],
+		) -> DispatchResult {
+			let caller = ensure_signed(origin)?;
+			let (borrower, order) =
+				Self::rental_of_token(registry, &token_id).ok_or(Error::<T>::NoneExist)?;
+			let lender = Self::account_of(order.lender)?;
+			let opened_by = if caller == lender {
+				DisputeParty::Lender
+			} else if caller == borrower {
+				DisputeParty::Borrower
+			} else {
+				return Err(Error::<T>::NotParty.into());
+			};
+			ensure!(
+				!Disputes::<T>::contains_key(&borrower, &token_id),
+				Error::<T>::AlreadyDisputed
+			);
+
+			let deposit = T::DisputeDeposit::get();
+			Self::pay_fee(&caller, &Self::escrow_account(&borrower, &token_id), None, deposit)?;
+			let dispute = Dispute {
+				opened_by,
+				evidence_hash,
+				opened_at: frame_system::Pallet::<T>::current_block_number(),
+				deposit,
+				escrow: 0,
+			};
+			Disputes::<T>::insert(borrower.clone(), token_id.clone(), dispute);
+			Self::deposit_event(Event::DisputeOpened(borrower, token_id, caller, evidence_hash));
+			Ok(())
+		}
+
+		/// Rule on a dispute: split the escrow, pay the deposit to the party the dispute is settled
+		/// for and continue, extend or terminate the rental.
+		#[pallet::weight(T::WeightInfo::resolve_dispute())]
+		pub fn resolve_dispute(
+			origin: OriginFor<T>,
+			borrower: T::AccountId,
+			token_id: Vec<u8>,
+			resolution: DisputeResolution,
+		) -> DispatchResult {
+			T::ArbiterOrigin::ensure_origin(origin)?;
+			let dispute = Disputes::<T>::take(borrower.clone(), token_id.clone())
+				.ok_or(Error::<T>::NoDispute)?;
+			let order = Borrowers::<T>::try_get(borrower.clone(), token_id.clone())
+				.map_err(|_| Error::<T>::NoneExist)?;
+			let lender = Self::account_of(order.lender)?;
+			let loser = match resolution.in_favour_of.other() {
+				DisputeParty::Lender => &lender,
+				DisputeParty::Borrower => &borrower,
+			};
+			Reputations::<T>::mutate(loser, |r| r.disputes_lost.saturating_inc());
+
+			Self::release_escrow(&borrower, &lender, &order, &dispute, &resolution)?;
+			match resolution.action {
+				DisputeAction::Terminate => {
+					Self::end_rental(&borrower, &lender, &order)?;
+					Self::deposit_event(Event::ReturnAsset(
+						borrower.clone(),
+						lender,
+						token_id.clone(),
+					));
+				},
+				DisputeAction::Extend { due_date } => {
+					ensure!(due_date > order.due_date, Error::<T>::TimeNotLongEnough);
+					let extended = Order { due_date, ..order };
+					Borrowers::<T>::insert(borrower.clone(), token_id.clone(), extended.clone());
+					Self::grant_usage(&token_id, &borrower, due_date);
+					Self::resume_rental(&borrower, &extended)?;
+				},
+				DisputeAction::Continue => Self::resume_rental(&borrower, &order)?,
+			}
+
+			let ruling = Ruling {
+				opened_by: dispute.opened_by,
+				evidence_hash: dispute.evidence_hash,
+				resolution,
+				escrow: dispute.escrow,
+				resolved_at: frame_system::Pallet::<T>::current_block_number(),
+			};
+			Rulings::<T>::insert(borrower.clone(), token_id.clone(), ruling);
+			Self::deposit_event(Event::DisputeResolved(borrower, token_id, resolution));
+			Ok(())
+		}
+
+		/// Remove stale entries, submitted unsigned by the off-chain worker. Entries cleaned up
+		/// since the transaction was validated are skipped.
+		#[pallet::weight(T::WeightInfo::cleanup(entries.len() as u32))]
+		pub fn cleanup(
+			origin: OriginFor<T>,
+			entries: Vec<StaleEntry<T::AccountId>>,
+		) -> DispatchResult {
+			ensure_none(origin)?;
+			let mut removed = 0u32;
+			for entry in entries.into_iter().filter(|entry| Self::is_stale(entry)) {
+				match entry {
+					StaleEntry::CancelledOrder(key) => CancelOrder::<T>::remove(key),
+					StaleEntry::ExpiredRental(borrower, token_id) => {
+						if let Err(e) = Self::do_return_asset(borrower, token_id.clone()) {
+							log::warn!("skip cleanup of token {:?}: {:?}", token_id, e);
+							continue;
+						}
+					},
+				}
+				removed.saturating_inc();
+			}
+			Self::deposit_event(Event::CleanedUp(removed));
+			Ok(())
+		}
+	}
+
+	#[pallet::validate_unsigned]
+	impl<T: Config> ValidateUnsigned for Pallet<T> {
+		type Call = Call<T>;
+
+		fn validate_unsigned(_source: TransactionSource, call: &Self::Call) -> TransactionValidity {
+			let entries = match call {
+				Call::cleanup { entries } => entries,
+				_ => return InvalidTransaction::Call.into(),
+			};
+			if entries.is_empty() || entries.len() > T::MaxCleanup::get() as usize {
+				return InvalidTransaction::ExhaustsResources.into();
+			}
+			if !entries.iter().all(Self::is_stale) {
+				return InvalidTransaction::Stale.into();
+			}
+			// two transactions cleaning up the same entry cannot both be included
+			entries
+				.iter()
+				.fold(ValidTransaction::with_tag_prefix("RentingCleanup"), |builder, entry| {
+					builder.and_provides(entry)
+				})
+				.priority(T::UnsignedPriority::get())
+				.longevity(T::CleanupInterval::get().saturated_into())
+				.propagate(true)
+				.build()
+		}
 	}
 }
 
 // helper functions
 impl<T: Config> Pallet<T> {
+	/// Ongoing rental of `token_id` by `borrower`, if any.
+	pub fn rental(borrower: &T::AccountId, token_id: &[u8]) -> Option<Order> {
+		Borrowers::<T>::try_get(borrower, token_id.to_vec()).ok()
+	}
+
+	/// Account allowed to use `token_id` now, the borrower of its rental until the due date.
+	pub fn user_of(token_id: &[u8]) -> Option<T::AccountId> {
+		let right = UsageRights::<T>::get(token_id)?;
+		(T::Timestamp::now().as_secs() < right.expires).then_some(right.user)
+	}
+
+	/// Unix time in seconds the usage right of `token_id` expires at.
+	pub fn user_expires(token_id: &[u8]) -> Option<u64> {
+		UsageRights::<T>::get(token_id).map(|right| right.expires)
+	}
+
 	fn verify_signature(
 		data: Vec<u8>,
 		signature: Vec<u8>,
@@ -322,69 +795,136 @@ impl<T: Config> Pallet<T> {
 		borrower: [u8; 32],
 		message: &Vec<u8>,
 	) -> Result<Order, DispatchError> {
-		let data = str::from_utf8(message).unwrap();
-		let order_data = parse_json(data).unwrap().to_object().unwrap();
+		let data = str::from_utf8(message).map_err(|_| Error::<T>::InvalidOrder)?;
+		let order_data = parse_json(data)
+			.ok()
+			.and_then(|json| json.to_object())
+			.ok_or(Error::<T>::InvalidOrder)?;
 		let mut order = Order {
 			lender: [0u8; 32],
 			borrower: [0u8; 32],
 			fee: 0,
 			token: vec![],
 			due_date: 0,
-			paid_type: 0,
+			billing: BillingPlan::Upfront,
+			asset_id: None,
+			min_reputation: 0,
+			credential: None,
+			registry: Registry::NftCurrency,
 		};
+		let mut billing: Option<Vec<u8>> = None;
+		let mut period: u64 = 0;
+		let mut first = FirstPayment::Upfront;
 
 		for data in order_data.into_iter() {
 			let key = data.0;
 			let k = key.iter().map(|c| *c as u8).collect::<Vec<_>>();
 
 			if k == "lender".as_bytes().to_vec() {
-				let value =
-					data.1.to_string().unwrap().iter().map(|c| *c as u8).collect::<Vec<_>>();
+				let value = Self::json_string(data.1)?;
 				let hex_account: T::AccountId =
-					convert_string_to_accountid(&String::from_utf8(value.clone()).unwrap());
-				let account: T::AccountId = convert_bytes_to_accountid(lender.clone());
+					convert_string_to_accountid(&value).ok_or(Error::<T>::InvalidAccount)?;
+				let account = Self::account_of(lender.clone())?;
 				ensure!(hex_account == account, Error::<T>::NotMatchLender);
 				order.lender = lender;
 			} else if k == "borrower".as_bytes().to_vec() {
-				let value =
-					data.1.to_string().unwrap().iter().map(|c| *c as u8).collect::<Vec<_>>();
+				let value = Self::json_string(data.1)?;
 				let hex_account: T::AccountId =
-					convert_string_to_accountid(&String::from_utf8(value.clone()).unwrap());
-				let account: T::AccountId = convert_bytes_to_accountid(borrower.clone());
+					convert_string_to_accountid(&value).ok_or(Error::<T>::InvalidAccount)?;
+				let account = Self::account_of(borrower.clone())?;
 				ensure!(hex_account == account, Error::<T>::NotMatchBorrower);
 				order.borrower = borrower;
 			} else if k == "fee".as_bytes().to_vec() {
-				let value = data.1.to_number().unwrap().integer;
+				let value = Self::json_integer(data.1)?;
 				order.fee = value;
 			} else if k == "token".as_bytes().to_vec() {
-				let value = String::from_utf8(
-					data.1.to_string().unwrap().iter().map(|c| *c as u8).collect::<Vec<_>>(),
-				)
-				.unwrap();
-				let token = hex_string_to_vec(value);
+				let value = Self::json_string(data.1)?;
+				let token = hex_string_to_vec(value).ok_or(Error::<T>::InvalidToken)?;
 				order.token = token;
 			} else if k == "due_date".as_bytes().to_vec() {
-				let value = data.1.to_number().unwrap().integer;
+				let value = Self::json_integer(data.1)?;
 				ensure!(value > T::Timestamp::now().as_secs(), Error::<T>::TimeOver);
 				order.due_date = value;
-			} else if k == "paid_type".as_bytes().to_vec() {
-				let value = data.1.to_number().unwrap().integer;
-				ensure!(value <= 2, Error::<T>::NotPaidType);
-				order.paid_type = value.saturated_into();
+			} else if k == "billing".as_bytes().to_vec() {
+				let value = Self::json_string(data.1)?;
+				billing = Some(value.into_bytes());
+			} else if k == "period".as_bytes().to_vec() {
+				period = Self::json_integer(data.1)?;
+			} else if k == "deferred".as_bytes().to_vec() {
+				if data.1.to_bool().ok_or(Error::<T>::InvalidOrder)? {
+					first = FirstPayment::Deferred;
+				}
+			} else if k == "asset_id".as_bytes().to_vec() {
+				let value = Self::json_integer(data.1)?;
+				order.asset_id = Some(value.saturated_into());
+			} else if k == "min_reputation".as_bytes().to_vec() {
+				let value = Self::json_integer(data.1)?;
+				order.min_reputation = value.saturated_into();
+			} else if k == "registry".as_bytes().to_vec() {
+				let value = Self::json_string(data.1)?;
+				order.registry = match value.as_str() {
+					"nft_currency" => Registry::NftCurrency,
+					"uniques" => Registry::Uniques,
+					_ => return Err(Error::<T>::InvalidRegistry.into()),
+				};
+			} else if k == "credential".as_bytes().to_vec() {
+				let value = Self::json_string(data.1)?;
+				order.credential = match value.as_str() {
+					"allowlist" => Some(Credential::Allowlist),
+					_ => return Err(Error::<T>::InvalidCredential.into()),
+				};
 			}
 		}
+		order.billing = match billing.as_deref() {
+			None | Some(b"upfront") => BillingPlan::Upfront,
+			Some(b"blocks") => {
+				ensure!(period > 0 && period <= u32::MAX as u64, Error::<T>::NotPaidType);
+				BillingPlan::EveryBlocks { period: period as u32, first }
+			},
+			Some(b"seconds") => {
+				ensure!(period > 0, Error::<T>::NotPaidType);
+				BillingPlan::EverySeconds { period, first }
+			},
+			Some(_) => return Err(Error::<T>::NotPaidType.into()),
+		};
 		Ok(order)
 	}
 
+	/// Read a JSON string made of bytes only, as every string of an order is.
+	fn json_string(value: JsonValue) -> Result<String, DispatchError> {
+		let chars = value.to_string().ok_or(Error::<T>::InvalidOrder)?;
+		let bytes = chars.iter().map(|c| *c as u8).collect::<Vec<_>>();
+		Ok(String::from_utf8(bytes).map_err(|_| Error::<T>::InvalidOrder)?)
+	}
+
+	/// Read a JSON number as an unsigned integer.
+	fn json_integer(value: JsonValue) -> Result<u64, DispatchError> {
+		Ok(value.to_number().ok_or(Error::<T>::InvalidOrder)?.integer)
+	}
+
+	/// Decode an account stored in an order.
+	fn account_of(bytes: [u8; 32]) -> Result<T::AccountId, DispatchError> {
+		Ok(convert_bytes_to_accountid(bytes).ok_or(Error::<T>::InvalidAccount)?)
+	}
+
 	fn match_order(
 		lender: T::AccountId,
+		borrower: &T::AccountId,
 		order_left: Order,
-		mut order_right: Order,
+		order_right: Order,
 	) -> Result<Order, DispatchError> {
 		ensure!(order_left.token == order_right.token, Error::<T>::NotMatchToken);
 		ensure!(order_left.lender == order_right.lender, Error::<T>::NotMatchLender);
 		ensure!(order_left.due_date >= order_right.due_date, Error::<T>::TimeOver);
 		ensure!(order_left.fee <= order_right.fee, Error::<T>::NotEnoughFee);
+		ensure!(order_left.asset_id == order_right.asset_id, Error::<T>::NotMatchAsset);
+		ensure!(order_left.billing == order_right.billing, Error::<T>::NotMatchBillingPlan);
+		ensure!(order_left.registry == order_right.registry, Error::<T>::NotMatchToken);
+		ensure!(
+			Self::reputation(borrower).qualifies_for(order_left.min_reputation),
+			Error::<T>::ReputationTooLow
+		);
+		ensure!(Self::holds(borrower, &lender, order_left.credential), Error::<T>::NotQualified);
 
 		let order = order_right.clone();
 		ensure!(
@@ -392,18 +932,64 @@ impl<T: Config> Pallet<T> {
 			Error::<T>::NotQualified
 		);
 
-		let total_renting_days = Self::calculate_day_renting(order_right.due_date);
-		ensure!(total_renting_days > 1, Error::<T>::TimeNotLongEnough);
+		ensure!(
+			Self::blocks_until(order_right.due_date) > Zero::zero(),
+			Error::<T>::TimeNotLongEnough
+		);
+
+		Ok(order_right)
+	}
 
-		if order_right.paid_type == 0 {
-			order_right.fee = order_right.fee * total_renting_days;
-		} else if order_right.paid_type == 1 {
-			order_right.fee = order_right.fee;
-		} else if order_right.paid_type == 2 {
-			order_right.fee = order_right.fee * 7;
+	/// Whether `borrower` holds the `credential` an order of `lender` requires.
+	fn holds(
+		borrower: &T::AccountId,
+		lender: &T::AccountId,
+		credential: Option<Credential>,
+	) -> bool {
+		match credential {
+			None => true,
+			Some(Credential::Allowlist) => Allowlists::<T>::contains_key(lender, borrower),
 		}
+	}
 
-		Ok(order_right)
+	/// Account holding `token_id` in `registry`.
+	fn custodian(registry: Registry, token_id: &[u8]) -> Option<T::AccountId> {
+		match registry {
+			Registry::NftCurrency => T::TokenNFT::custodian_of_token(token_id.to_vec()),
+			Registry::Uniques => T::Uniques::custodian_of_token(token_id.to_vec()),
+		}
+	}
+
+	/// Hand `token_id` of `registry` from `from` over to `to`, leaving its owner unchanged.
+	fn move_custody(
+		registry: Registry,
+		from: &T::AccountId,
+		to: &T::AccountId,
+		token_id: &[u8],
+	) -> DispatchResult {
+		match registry {
+			Registry::NftCurrency => {
+				T::TokenNFT::transfer_custodian(from.clone(), to.clone(), token_id.to_vec())
+			},
+			Registry::Uniques => {
+				T::Uniques::transfer_custodian(from.clone(), to.clone(), token_id.to_vec())
+			},
+		}
+	}
+
+	/// Borrower and order of the ongoing rental of `token_id` in `registry`.
+	fn rental_of_token(registry: Registry, token_id: &[u8]) -> Option<(T::AccountId, Order)> {
+		let borrower = Self::custodian(registry, token_id)?;
+		let order = Self::rental(&borrower, token_id)?;
+		(order.registry == registry).then(|| (borrower, order))
+	}
+
+	/// Whether `token_id` still exists in `registry`, the lender may burn a rented token.
+	fn token_exists(registry: Registry, token_id: &[u8]) -> bool {
+		match registry {
+			Registry::NftCurrency => T::TokenNFT::owner_of_token(token_id.to_vec()).is_some(),
+			Registry::Uniques => T::Uniques::owner_of_token(token_id.to_vec()).is_some(),
+		}
 	}
 
 	fn transfer_custodian(
@@ -412,55 +998,547 @@ impl<T: Config> Pallet<T> {
 		order: Order,
 	) -> DispatchResult {
 		ensure!(
-			!T::TokenNFT::transfer_custodian(lender.clone(), borrower.clone(), order.token.clone())
-				.is_err(),
+			Self::move_custody(order.registry, lender, borrower, &order.token).is_ok(),
 			Error::<T>::CannotTransferCustodian
 		);
-		let _ = T::Currency::transfer(
+		if order.billing.pays_upfront() {
+			Self::pay_rental(borrower, lender, &order, order.fee)?;
+		}
+		Ok(())
+	}
+
+	/// Return the asset of a rental once its due date is reached.
+	#[transactional]
+	fn do_return_asset(borrower: T::AccountId, token_id: Vec<u8>) -> DispatchResult {
+		let order = match Borrowers::<T>::try_get(borrower.clone(), token_id.clone()) {
+			Ok(order) => order,
+			Err(_) => return Ok(()),
+		};
+		// the asset stays with the borrower until the arbiter rules
+		if Disputes::<T>::contains_key(&borrower, &token_id) {
+			return Ok(());
+		}
+		let lender = Self::account_of(order.lender)?;
+		if !Self::token_exists(order.registry, &token_id) {
+			return Self::end_burned_rental(&borrower, &lender, &order);
+		}
+		// blocks came faster than expected, wait until the due date is really reached; slower
+		// blocks are not caught up with, see `return_asset`
+		if T::Timestamp::now().as_secs() < order.due_date {
+			let now = frame_system::Pallet::<T>::current_block_number();
+			let remaining = Self::blocks_until(order.due_date).max(One::one());
+			return Self::schedule_task(
+				RentalTask::Return,
+				now.saturating_add(remaining),
+				RETURN_PRIORITY,
+				&borrower,
+				&token_id,
+			);
+		}
+		// a missed instalment, the last one of a deferred plan included, gets a final attempt; a
+		// borrower who cannot pay it defaults, the missed repayment stays on its record
+		let defaulted = match Self::overdue(borrower.clone(), token_id.clone()) {
+			Some(overdue) => {
+				let paid = Self::pay_overdue(&borrower, &lender, &order, &overdue).is_ok();
+				if paid {
+					Self::deposit_event(Event::RepaymentRental(
+						borrower.clone(),
+						lender.clone(),
+						token_id.clone(),
+					));
+				}
+				!paid
+			},
+			None => false,
+		};
+		Self::end_rental(&borrower, &lender, &order)?;
+		// renting to oneself costs nothing but the marketplace cut, it builds no track record
+		if borrower != lender {
+			Reputations::<T>::mutate(&lender, |r| r.completed.saturating_inc());
+			if !defaulted {
+				Reputations::<T>::mutate(&borrower, |r| r.completed.saturating_inc());
+			}
+		}
+		Self::deposit_event(Event::ReturnAsset(borrower, lender, order.token));
+		Ok(())
+	}
+
+	/// Collect the instalment of a periodic rental, or record it as overdue.
+	#[transactional]
+	fn do_collect_repayment(borrower: T::AccountId, token_id: Vec<u8>) -> DispatchResult {
+		let order = match Borrowers::<T>::try_get(borrower.clone(), token_id.clone()) {
+			Ok(order) => order,
+			Err(_) => return Ok(()),
+		};
+		let lender = Self::account_of(order.lender)?;
+		// nothing is left to pay for
+		if !Self::token_exists(order.registry, &token_id) {
+			return Self::end_burned_rental(&borrower, &lender, &order);
+		}
+		let now = frame_system::Pallet::<T>::current_block_number();
+		Self::schedule_next_repayment(now, &borrower, &order)?;
+
+		// instalments falling due during the grace period are settled by the retry
+		if let Some(mut overdue) = Self::overdue(borrower.clone(), token_id.clone()) {
+			overdue.amount = overdue.amount.saturating_add(order.fee);
+			Overdue::<T>::insert(&borrower, token_id, overdue);
+			Reputations::<T>::mutate(&borrower, |r| r.missed_repayments.saturating_inc());
+			return Ok(());
+		}
+		if Self::pay_rental(&borrower, &lender, &order, order.fee).is_err() {
+			Reputations::<T>::mutate(&borrower, |r| r.missed_repayments.saturating_inc());
+			let overdue = OverduePayment {
+				amount: order.fee,
+				late_fee: T::LateFee::get().mul_floor(order.fee),
+				attempts: 1,
+			};
+			Self::schedule_retry(now, borrower, lender, order, overdue)?;
+		} else {
+			Self::deposit_event(Event::RepaymentRental(borrower, lender, token_id));
+		}
+		Ok(())
+	}
+
+	/// Settle an overdue instalment, or take the asset back once the retries are exhausted.
+	#[transactional]
+	fn do_retry_payment(borrower: T::AccountId, token_id: Vec<u8>) -> DispatchResult {
+		let order = match Borrowers::<T>::try_get(borrower.clone(), token_id.clone()) {
+			Ok(order) => order,
+			Err(_) => return Ok(()),
+		};
+		let overdue = match Self::overdue(borrower.clone(), token_id.clone()) {
+			Some(overdue) => overdue,
+			None => return Ok(()),
+		};
+		let lender = Self::account_of(order.lender)?;
+		if !Self::token_exists(order.registry, &token_id) {
+			return Self::end_burned_rental(&borrower, &lender, &order);
+		}
+		if Self::pay_overdue(&borrower, &lender, &order, &overdue).is_ok() {
+			Overdue::<T>::remove(borrower.clone(), token_id.clone());
+			Self::deposit_event(Event::RepaymentRental(borrower, lender, token_id));
+		} else if Disputes::<T>::contains_key(&borrower, &token_id) {
+			// the arbiter decides what happens to the overdue rent, retried once the rental resumes
+			return Ok(());
+		} else if overdue.attempts >= T::MaxPaymentRetries::get() {
+			// grace period exhausted, take the asset back
+			Self::end_rental(&borrower, &lender, &order)?;
+			Self::deposit_event(Event::ReturnAsset(borrower, lender, token_id));
+		} else {
+			let now = frame_system::Pallet::<T>::current_block_number();
+			let overdue = OverduePayment {
+				late_fee: overdue.late_fee.saturating_add(T::LateFee::get().mul_floor(order.fee)),
+				attempts: overdue.attempts.saturating_add(1),
+				..overdue
+			};
+			Self::schedule_retry(now, borrower, lender, order, overdue)?;
+		}
+		Ok(())
+	}
+
+	/// Pay `amount` of rent for `order`: the marketplace cut goes to `T::FeeDestination` and the
+	/// rest to the lender.
+	#[transactional]
+	fn pay_rental(
+		borrower: &T::AccountId,
+		lender: &T::AccountId,
+		order: &Order,
+		amount: u64,
+	) -> DispatchResult {
+		let destination = T::FeeDestination::get();
+		let mut marketplace_cut = Self::marketplace_fee().mul_floor(amount);
+		// a cut too small to create the fee account with is left to the lender
+		if !Self::can_receive(&destination, order.asset_id, marketplace_cut) {
+			marketplace_cut = 0;
+		}
+		if marketplace_cut > 0 {
+			Self::pay_fee(borrower, &destination, order.asset_id, marketplace_cut)?;
+			Self::deposit_event(Event::MarketplaceFeeTaken(
+				borrower.clone(),
+				order.token.clone(),
+				marketplace_cut,
+			));
+		}
+		Self::pay_lender(borrower, lender, order, amount.saturating_sub(marketplace_cut))
+	}
+
+	/// Pay `amount` owed to the lender of `order`, into the escrow while the rental is disputed.
+	fn pay_lender(
+		borrower: &T::AccountId,
+		lender: &T::AccountId,
+		order: &Order,
+		amount: u64,
+	) -> DispatchResult {
+		match Self::disputes(borrower, &order.token) {
+			Some(mut dispute) => {
+				let escrow = Self::escrow_account(borrower, &order.token);
+				Self::pay_fee(borrower, &escrow, order.asset_id, amount)?;
+				dispute.escrow = dispute.escrow.saturating_add(amount);
+				Disputes::<T>::insert(borrower, &order.token, dispute);
+				Ok(())
+			},
+			None => Self::pay_fee(borrower, lender, order.asset_id, amount),
+		}
+	}
+
+	/// Settle the missed instalments of `order` together with the late fee owed to the lender.
+	#[transactional]
+	fn pay_overdue(
+		borrower: &T::AccountId,
+		lender: &T::AccountId,
+		order: &Order,
+		overdue: &OverduePayment,
+	) -> DispatchResult {
+		Self::pay_rental(borrower, lender, order, overdue.amount)?;
+		Self::pay_lender(borrower, lender, order, overdue.late_fee)
+	}
+
+	/// Record a missed payment and schedule the next attempt within the grace period.
+	fn schedule_retry(
+		now: T::BlockNumber,
+		borrower: T::AccountId,
+		lender: T::AccountId,
+		order: Order,
+		overdue: OverduePayment,
+	) -> DispatchResult {
+		let retries = T::MaxPaymentRetries::get().max(1);
+		let interval = (T::GracePeriod::get() / retries.into()).max(One::one());
+		Self::deposit_event(Event::PaymentOverdue(
+			borrower.clone(),
+			lender,
+			order.token.clone(),
+			overdue.attempts,
+			overdue.late_fee,
+		));
+		Overdue::<T>::insert(borrower.clone(), order.token.clone(), overdue);
+		Self::schedule_task(
+			RentalTask::Retry,
+			now.saturating_add(interval),
+			PAYMENT_PRIORITY,
 			&borrower,
-			&lender,
-			order.fee.saturated_into(),
-			ExistenceRequirement::KeepAlive,
+			&order.token,
 		)
-		.unwrap();
+	}
+
+	/// Transfer `amount` from `from` to `to`, either in the native currency or in the asset the
+	/// order is priced in.
+	fn pay_fee(
+		from: &T::AccountId,
+		to: &T::AccountId,
+		asset_id: Option<u32>,
+		amount: u64,
+	) -> DispatchResult {
+		match asset_id {
+			Some(asset_id) => {
+				<T::Assets as fungibles::Transfer<_>>::transfer(
+					asset_id,
+					from,
+					to,
+					amount.saturated_into(),
+					true,
+				)
+				.map_err(|_| Error::<T>::CannotPayFee)?;
+			},
+			None => {
+				T::Currency::transfer(
+					from,
+					to,
+					amount.saturated_into(),
+					ExistenceRequirement::KeepAlive,
+				)
+				.map_err(|_| Error::<T>::CannotPayFee)?;
+			},
+		}
 		Ok(())
 	}
 
-	fn calculate_day_renting(due_date: u64) -> u64 {
-		let part = due_date - T::Timestamp::now().as_secs();
-		part / 86400
+	/// Whether `to` can be paid `amount`, which has to reach the minimum balance of the native
+	/// currency or of the asset when the account does not hold any yet.
+	fn can_receive(to: &T::AccountId, asset_id: Option<u32>, amount: u64) -> bool {
+		match asset_id {
+			Some(asset_id) => {
+				!<T::Assets as fungibles::Inspect<_>>::balance(asset_id, to).is_zero()
+					|| <T::Assets as fungibles::Inspect<_>>::minimum_balance(asset_id)
+						<= amount.saturated_into()
+			},
+			None => {
+				!T::Currency::total_balance(to).is_zero()
+					|| T::Currency::minimum_balance() <= amount.saturated_into()
+			},
+		}
 	}
 
-	fn get_due_block(order: Order) -> T::BlockNumber {
-		let mut current_block_number = frame_system::Pallet::<T>::current_block_number();
-		let total_renting_days = Self::calculate_day_renting(order.due_date) as u32;
-		log::info!("total_renting_days: {}", total_renting_days);
-		let target_block = current_block_number + (total_renting_days * DAYS).into();
+	/// Number of whole blocks expected to be produced before the Unix time `due_date`.
+	fn blocks_until(due_date: u64) -> T::BlockNumber {
+		let now = T::Timestamp::now().as_millis() as u64;
+		let remaining = due_date.saturating_mul(1000).saturating_sub(now);
+		(remaining / T::BlockTime::get().max(1)).saturated_into()
+	}
 
-		if order.paid_type == 1 {
-			loop {
-				current_block_number += DAYS.into();
-				Repayment::<T>::mutate(current_block_number.clone(), |orders| {
-					orders.push(order.clone())
-				});
-				log::info!("block {:?}",current_block_number);
-				if current_block_number >= target_block {
-					break;
-				}
-			}
-		} else if order.paid_type == 2 {
-			loop {
-				current_block_number += WEEKS.into();
-				Repayment::<T>::mutate(current_block_number.clone(), |orders| {
-					orders.push(order.clone())
-				});
-				log::info!("block {:?}",current_block_number);
-				if current_block_number >= target_block {
-					break;
-				}
+	/// Length of one billing period in blocks, `None` if the whole fee is paid upfront.
+	fn billing_period(billing: &BillingPlan) -> Option<T::BlockNumber> {
+		match billing {
+			BillingPlan::Upfront => None,
+			BillingPlan::EveryBlocks { period, .. } => Some((*period).into()),
+			BillingPlan::EverySeconds { period, .. } => {
+				let blocks = (period.saturating_mul(1000) / T::BlockTime::get().max(1)).max(1);
+				Some(blocks.saturated_into())
+			},
+		}
+	}
+
+	/// Schedule the return of the asset at the due block and the first periodic instalment.
+	fn schedule_rental(borrower: &T::AccountId, order: &Order) -> DispatchResult {
+		let now = frame_system::Pallet::<T>::current_block_number();
+		let due_block = now.saturating_add(Self::blocks_until(order.due_date));
+		log::info!("due block: {:?}", due_block);
+		Self::schedule_task(
+			RentalTask::Return,
+			due_block,
+			RETURN_PRIORITY,
+			borrower,
+			&order.token,
+		)?;
+		Self::schedule_next_repayment(now, borrower, order)
+	}
+
+	/// Schedule the instalment following block `now`. Upfront plans pay at the start of every
+	/// period before the due block, deferred plans at the end of every period including the last.
+	fn schedule_next_repayment(
+		now: T::BlockNumber,
+		borrower: &T::AccountId,
+		order: &Order,
+	) -> DispatchResult {
+		let period = match Self::billing_period(&order.billing) {
+			Some(period) => period,
+			None => return Ok(()),
+		};
+		let due_block = match T::Scheduler::next_dispatch_time(Self::task_id(
+			RentalTask::Return,
+			borrower,
+			&order.token,
+		)) {
+			Ok(due_block) => due_block,
+			Err(_) => return Ok(()),
+		};
+		let next_payment = now.saturating_add(period);
+		let when = if next_payment < due_block {
+			next_payment
+		} else if !order.billing.pays_upfront() && now < due_block {
+			due_block
+		} else {
+			return Ok(());
+		};
+		Self::schedule_task(RentalTask::Repayment, when, PAYMENT_PRIORITY, borrower, &order.token)
+	}
+
+	/// Give the asset of `order` back to the lender and drop what is left of the rental.
+	fn end_rental(borrower: &T::AccountId, lender: &T::AccountId, order: &Order) -> DispatchResult {
+		if Self::token_exists(order.registry, &order.token) {
+			Self::move_custody(order.registry, borrower, lender, &order.token)?;
+		} else {
+			// a burned item keeps no custody, and must not lock an item minted with its id
+			ItemCustodians::<T>::remove(&order.token);
+		}
+		Borrowers::<T>::remove(borrower, &order.token);
+		// a sublet token goes back to the borrower it was sublet by
+		match Self::rental(lender, &order.token) {
+			Some(sublet) => Self::grant_usage(&order.token, lender, sublet.due_date),
+			None => {
+				UsageRights::<T>::remove(&order.token);
+				Self::deposit_event(Event::UpdateUser(order.token.clone(), None, 0));
+			},
+		}
+		Overdue::<T>::remove(borrower, &order.token);
+		for task in [RentalTask::Return, RentalTask::Repayment, RentalTask::Retry] {
+			// the task being dispatched right now is no longer known to the scheduler
+			let _ = T::Scheduler::cancel_named(Self::task_id(task, borrower, &order.token));
+		}
+		Ok(())
+	}
+
+	/// End the rental of a burned token. Nothing is left to return, the instalments stop and the
+	/// rental counts for neither party.
+	fn end_burned_rental(
+		borrower: &T::AccountId,
+		lender: &T::AccountId,
+		order: &Order,
+	) -> DispatchResult {
+		Self::end_rental(borrower, lender, order)?;
+		Self::deposit_event(Event::RentalTokenBurned(
+			borrower.clone(),
+			lender.clone(),
+			order.token.clone(),
+		));
+		Ok(())
+	}
+
+	/// Mint the usage right of `token_id` to `user`, replacing the one it may have.
+	fn grant_usage(token_id: &[u8], user: &T::AccountId, expires: u64) {
+		UsageRights::<T>::insert(token_id, UsageRight { user: user.clone(), expires });
+		Self::deposit_event(Event::UpdateUser(token_id.to_vec(), Some(user.clone()), expires));
+	}
+
+	/// Name of a scheduler task, unique per rental.
+	fn task_id(task: RentalTask, borrower: &T::AccountId, token_id: &[u8]) -> [u8; 32] {
+		(b"renting", task, borrower, token_id).using_encoded(sp_io::hashing::blake2_256)
+	}
+
+	/// Schedule the call running `task` for the rental of `token_id` at block `when`, or at the
+	/// first of the following `MAX_SCHEDULE_DELAY` blocks whose agenda still has room.
+	fn schedule_task(
+		task: RentalTask,
+		when: T::BlockNumber,
+		priority: Priority,
+		borrower: &T::AccountId,
+		token_id: &[u8],
+	) -> DispatchResult {
+		let call = match task {
+			RentalTask::Return => {
+				Call::return_asset { borrower: borrower.clone(), token_id: token_id.to_vec() }
+			},
+			RentalTask::Repayment => {
+				Call::collect_repayment { borrower: borrower.clone(), token_id: token_id.to_vec() }
+			},
+			RentalTask::Retry => {
+				Call::retry_payment { borrower: borrower.clone(), token_id: token_id.to_vec() }
+			},
+		};
+		let call = T::Preimages::bound(CallOf::<T>::from(call))?;
+		let mut result = Ok(());
+		for delay in 0..=MAX_SCHEDULE_DELAY {
+			result = T::Scheduler::schedule_named(
+				Self::task_id(task, borrower, token_id),
+				DispatchTime::At(when.saturating_add(delay.into())),
+				None,
+				priority,
+				frame_system::RawOrigin::Root.into(),
+				call.clone(),
+			)
+			.map(|_| ());
+			if result.is_ok() {
+				break;
 			}
 		}
-		target_block
+		result
+	}
+
+	/// Account holding the deposit and the escrowed rent of a disputed rental.
+	fn escrow_account(borrower: &T::AccountId, token_id: &[u8]) -> T::AccountId {
+		let seed = (b"escrow", borrower, token_id).using_encoded(sp_io::hashing::blake2_256);
+		T::PalletId::get().into_sub_account_truncating(seed)
+	}
+
+	/// Pay out the escrow of a dispute as ruled by the arbiter. The loser is paid its share first
+	/// and the winner the rest, the deposit and a loser share that cannot be paid on its own
+	/// included.
+	fn release_escrow(
+		borrower: &T::AccountId,
+		lender: &T::AccountId,
+		order: &Order,
+		dispute: &Dispute<T::BlockNumber>,
+		resolution: &DisputeResolution,
+	) -> DispatchResult {
+		let escrow = Self::escrow_account(borrower, &order.token);
+		let to_lender = resolution.lender_share.mul_floor(dispute.escrow);
+		let (winner, loser, loser_share) = match resolution.in_favour_of {
+			DisputeParty::Lender => (lender, borrower, dispute.escrow.saturating_sub(to_lender)),
+			DisputeParty::Borrower => (borrower, lender, to_lender),
+		};
+		let mut rest = dispute.escrow;
+		if loser_share > 0 && Self::can_receive(loser, order.asset_id, loser_share) {
+			// the deposit keeps a native escrow alive, an asset escrow is emptied when the winner
+			// share is too small to be left in it
+			let winner_share = dispute.escrow.saturating_sub(loser_share);
+			let dust = match order.asset_id {
+				Some(asset_id) => {
+					<T::Assets as fungibles::Inspect<_>>::minimum_balance(asset_id)
+						> winner_share.saturated_into()
+				},
+				None => false,
+			};
+			Self::release(&escrow, loser, order.asset_id, loser_share, !dust)?;
+			rest = if dust { 0 } else { winner_share };
+		}
+		match order.asset_id {
+			None => {
+				Self::release(&escrow, winner, None, rest.saturating_add(dispute.deposit), false)
+			},
+			Some(_) => {
+				Self::release(&escrow, winner, order.asset_id, rest, false)?;
+				Self::release(&escrow, winner, None, dispute.deposit, false)
+			},
+		}
+	}
+
+	/// Transfer `amount` out of an escrow account, which may be emptied unless `keep_alive`. An
+	/// asset balance left below the minimum is paid along with `amount`.
+	fn release(
+		escrow: &T::AccountId,
+		to: &T::AccountId,
+		asset_id: Option<u32>,
+		amount: u64,
+		keep_alive: bool,
+	) -> DispatchResult {
+		if amount == 0 {
+			return Ok(());
+		}
+		match asset_id {
+			None => T::Currency::transfer(
+				escrow,
+				to,
+				amount.saturated_into(),
+				if keep_alive {
+					ExistenceRequirement::KeepAlive
+				} else {
+					ExistenceRequirement::AllowDeath
+				},
+			),
+			Some(asset_id) => <T::Assets as fungibles::Transfer<_>>::transfer(
+				asset_id,
+				escrow,
+				to,
+				amount.saturated_into(),
+				keep_alive,
+			)
+			.map(|_| ()),
+		}
+	}
+
+	/// Pick a rental up after a dispute: schedule its return, the next instalment and the retry of
+	/// the rent that went overdue meanwhile.
+	fn resume_rental(borrower: &T::AccountId, order: &Order) -> DispatchResult {
+		let now = frame_system::Pallet::<T>::current_block_number();
+		let due_block = now.saturating_add(Self::blocks_until(order.due_date).max(One::one()));
+		let return_task = Self::task_id(RentalTask::Return, borrower, &order.token);
+		if T::Scheduler::next_dispatch_time(return_task).is_ok() {
+			T::Scheduler::reschedule_named(return_task, DispatchTime::At(due_block))?;
+		} else {
+			Self::schedule_task(
+				RentalTask::Return,
+				due_block,
+				RETURN_PRIORITY,
+				borrower,
+				&order.token,
+			)?;
+		}
+		let repayment = Self::task_id(RentalTask::Repayment, borrower, &order.token);
+		if T::Scheduler::next_dispatch_time(repayment).is_err() {
+			Self::schedule_next_repayment(now, borrower, order)?;
+		}
+		let retry = Self::task_id(RentalTask::Retry, borrower, &order.token);
+		if Overdue::<T>::contains_key(borrower, &order.token)
+			&& T::Scheduler::next_dispatch_time(retry).is_err()
+		{
+			Self::schedule_task(
+				RentalTask::Retry,
+				now.saturating_add(One::one()),
+				PAYMENT_PRIORITY,
+				borrower,
+				&order.token,
+			)?;
+		}
+		Ok(())
 	}
 
 	fn check_borrowers(user: T::AccountId, token_id: Vec<u8>, check_date: u64) -> bool {
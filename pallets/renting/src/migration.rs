@@ -0,0 +1,184 @@
+//! Storage migrations of the renting pallet.
+
+use super::*;
+use frame_support::{
+	storage_alias,
+	traits::{GetStorageVersion, OnRuntimeUpgrade, StorageVersion},
+};
+use sp_std::{collections::btree_map::BTreeMap, marker::PhantomData};
+
+/// Rentals run by the scheduler instead of the `DueBlock` and `Repayment` hooks.
+pub mod v1 {
+	use super::*;
+
+	/// `Order` as stored before billing plans, assets, credentials and registries.
+	#[derive(Clone, Encode, Decode, PartialEq, Debug)]
+	pub struct OldOrder {
+		pub(crate) lender: [u8; 32],
+		pub(crate) borrower: [u8; 32],
+		pub(crate) fee: u64,
+		pub(crate) token: Vec<u8>,
+		pub(crate) due_date: u64,
+		pub(crate) paid_type: u8, // at once :0, per day: 1, per week:2
+	}
+
+	impl OldOrder {
+		/// Order a message of the old layout parses to now, it names no billing plan.
+		fn parsed(&self) -> Order {
+			Order {
+				lender: self.lender,
+				borrower: self.borrower,
+				fee: self.fee,
+				token: self.token.clone(),
+				due_date: self.due_date,
+				..Order::new()
+			}
+		}
+
+		/// Ongoing rental of the order, still paying the instalment of its period.
+		fn rental(&self) -> Order {
+			let billing = match self.paid_type {
+				1 => BillingPlan::EverySeconds { period: 86_400, first: FirstPayment::Upfront },
+				2 => BillingPlan::EverySeconds { period: 604_800, first: FirstPayment::Upfront },
+				_ => BillingPlan::Upfront,
+			};
+			Order { billing, ..self.parsed() }
+		}
+	}
+
+	#[storage_alias]
+	pub(crate) type DueBlock<T: Config> = StorageMap<
+		Pallet<T>,
+		Blake2_128Concat,
+		<T as frame_system::Config>::BlockNumber,
+		Vec<OldOrder>,
+		ValueQuery,
+	>;
+
+	#[storage_alias]
+	pub(crate) type Repayment<T: Config> = StorageMap<
+		Pallet<T>,
+		Blake2_128Concat,
+		<T as frame_system::Config>::BlockNumber,
+		Vec<OldOrder>,
+		ValueQuery,
+	>;
+
+	#[storage_alias]
+	pub(crate) type CancelOrder<T: Config> =
+		StorageMap<Pallet<T>, Blake2_128Concat, Vec<u8>, OldOrder, OptionQuery>;
+
+	/// Decode the stored orders into the current layout and hand every ongoing rental over to
+	/// the scheduler: its return at the block `DueBlock` had it at, its next instalment at the
+	/// first block `Repayment` had it at. Both maps are removed, cancelled orders are keyed by
+	/// the order their message parses to now.
+	pub struct MigrateToV1<T>(PhantomData<T>);
+
+	impl<T: Config> OnRuntimeUpgrade for MigrateToV1<T> {
+		fn on_runtime_upgrade() -> Weight {
+			if Pallet::<T>::on_chain_storage_version() >= 1 {
+				log::info!("renting storage is already at version 1, skip the migration");
+				return T::DbWeight::get().reads(1);
+			}
+			let mut reads = 1u64;
+			let mut writes = 1u64;
+			// run before the block is initialized, the scheduler takes tasks from the next block
+			let next_block = frame_system::Pallet::<T>::block_number().saturating_add(One::one());
+
+			let mut due_blocks = BTreeMap::new();
+			for (block, orders) in DueBlock::<T>::drain() {
+				reads.saturating_inc();
+				writes.saturating_inc();
+				for order in orders {
+					due_blocks.insert((order.borrower, order.token), block);
+				}
+			}
+			let mut repayments = BTreeMap::<_, T::BlockNumber>::new();
+			for (block, orders) in Repayment::<T>::drain() {
+				reads.saturating_inc();
+				writes.saturating_inc();
+				for order in orders {
+					let next = repayments.entry((order.borrower, order.token)).or_insert(block);
+					*next = (*next).min(block);
+				}
+			}
+
+			Borrowers::<T>::translate::<OldOrder, _>(|_, _, old| {
+				reads.saturating_inc();
+				writes.saturating_inc();
+				Some(old.rental())
+			});
+			for (borrower, token_id, order) in Borrowers::<T>::iter().collect::<Vec<_>>() {
+				let key = (order.borrower, token_id.clone());
+				let due_block = due_blocks
+					.get(&key)
+					.copied()
+					.unwrap_or_else(|| {
+						next_block.saturating_add(Pallet::<T>::blocks_until(order.due_date))
+					})
+					.max(next_block);
+				let scheduled = Pallet::<T>::schedule_task(
+					RentalTask::Return,
+					due_block,
+					RETURN_PRIORITY,
+					&borrower,
+					&token_id,
+				)
+				.and_then(|_| match repayments.get(&key) {
+					Some(&when) if when < due_block => Pallet::<T>::schedule_task(
+						RentalTask::Repayment,
+						when.max(next_block),
+						PAYMENT_PRIORITY,
+						&borrower,
+						&token_id,
+					),
+					_ => Ok(()),
+				});
+				if let Err(e) = scheduled {
+					log::error!("failed to schedule the rental of token {:?}: {:?}", token_id, e);
+				}
+				Pallet::<T>::grant_usage(&token_id, &borrower, order.due_date);
+				writes.saturating_accrue(3);
+			}
+
+			for (_, old) in CancelOrder::<T>::drain().collect::<Vec<_>>() {
+				reads.saturating_inc();
+				writes.saturating_accrue(2);
+				let order = old.parsed();
+				crate::CancelOrder::<T>::insert(order.encode(), order);
+			}
+
+			StorageVersion::new(1).put::<Pallet<T>>();
+			T::DbWeight::get().reads_writes(reads, writes)
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn pre_upgrade() -> Result<Vec<u8>, &'static str> {
+			ensure!(
+				Pallet::<T>::on_chain_storage_version() == 0,
+				"renting storage is not at version 0"
+			);
+			Ok((Borrowers::<T>::iter_keys().count() as u32).encode())
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn post_upgrade(state: Vec<u8>) -> Result<(), &'static str> {
+			let rentals = u32::decode(&mut &state[..]).map_err(|_| "invalid pre-upgrade state")?;
+			ensure!(
+				Pallet::<T>::on_chain_storage_version() == 1,
+				"renting storage is not at version 1"
+			);
+			ensure!(Borrowers::<T>::iter().count() as u32 == rentals, "rentals were lost");
+			ensure!(
+				Borrowers::<T>::iter_keys().all(|(borrower, token_id)| {
+					let task = Pallet::<T>::task_id(RentalTask::Return, &borrower, &token_id);
+					T::Scheduler::next_dispatch_time(task).is_ok()
+				}),
+				"a rental has no scheduled return"
+			);
+			ensure!(DueBlock::<T>::iter_keys().next().is_none(), "DueBlock is not empty");
+			ensure!(Repayment::<T>::iter_keys().next().is_none(), "Repayment is not empty");
+			Ok(())
+		}
+	}
+}
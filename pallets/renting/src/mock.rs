@@ -0,0 +1,314 @@
+use crate as pallet_renting;
+use frame_support::{
+	parameter_types,
+	traits::{
+		AsEnsureOriginWithArg, ConstU128, ConstU16, ConstU32, ConstU64, EqualPrivilegeOnly,
+		Randomness,
+	},
+	weights::Weight,
+	PalletId,
+};
+use frame_system::{EnsureRoot, EnsureSigned};
+use sp_core::{
+	crypto::{Ss58Codec, UncheckedFrom},
+	sr25519, Pair, H256,
+};
+use sp_keystore::{testing::KeyStore, KeystoreExt};
+use sp_runtime::{
+	testing::{Header, TestXt},
+	traits::{BlakeTwo256, Hash, IdentityLookup},
+	AccountId32, MultiSignature, MultiSigner, Permill,
+};
+use std::sync::Arc;
+
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
+type Block = frame_system::mocking::MockBlock<Test>;
+/// Transactions the off-chain worker submits to the pool.
+pub type Extrinsic = TestXt<RuntimeCall, ()>;
+
+pub type AccountId = AccountId32;
+pub type Balance = u128;
+
+/// Unix time of the first block, in milliseconds.
+pub const GENESIS_TIME: u64 = 1_700_000_000_000;
+pub const BLOCK_TIME: u64 = 6_000;
+pub const DISPUTE_DEPOSIT: u64 = 500;
+
+// Configure a mock runtime to test the pallet.
+frame_support::construct_runtime!(
+	pub enum Test where
+		Block = Block,
+		NodeBlock = Block,
+		UncheckedExtrinsic = UncheckedExtrinsic,
+	{
+		System: frame_system,
+		Timestamp: pallet_timestamp,
+		Balances: pallet_balances,
+		Assets: pallet_assets,
+		Preimage: pallet_preimage,
+		Scheduler: pallet_scheduler,
+		NftCurrency: pallet_nft_currency,
+		Uniques: pallet_uniques,
+		Renting: pallet_renting,
+	}
+);
+
+impl frame_system::Config for Test {
+	type BaseCallFilter = frame_support::traits::Everything;
+	type BlockWeights = ();
+	type BlockLength = ();
+	type DbWeight = ();
+	type RuntimeOrigin = RuntimeOrigin;
+	type RuntimeCall = RuntimeCall;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	type AccountId = AccountId;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type RuntimeEvent = RuntimeEvent;
+	type BlockHashCount = ConstU64<250>;
+	type Version = ();
+	type PalletInfo = PalletInfo;
+	type AccountData = pallet_balances::AccountData<Balance>;
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+	type SystemWeightInfo = ();
+	type SS58Prefix = ConstU16<42>;
+	type OnSetCode = ();
+	type MaxConsumers = ConstU32<16>;
+}
+
+impl pallet_timestamp::Config for Test {
+	type Moment = u64;
+	type OnTimestampSet = ();
+	type MinimumPeriod = ConstU64<{ BLOCK_TIME / 2 }>;
+	type WeightInfo = ();
+}
+
+impl pallet_balances::Config for Test {
+	type MaxLocks = ConstU32<50>;
+	type MaxReserves = ();
+	type ReserveIdentifier = [u8; 8];
+	type Balance = Balance;
+	type RuntimeEvent = RuntimeEvent;
+	type DustRemoval = ();
+	type ExistentialDeposit = ConstU128<1>;
+	type AccountStore = System;
+	type WeightInfo = ();
+}
+
+impl pallet_assets::Config for Test {
+	type RuntimeEvent = RuntimeEvent;
+	type Balance = u128;
+	type AssetId = u32;
+	type Currency = Balances;
+	type ForceOrigin = EnsureRoot<AccountId>;
+	type AssetDeposit = ConstU128<1>;
+	type AssetAccountDeposit = ConstU128<1>;
+	type MetadataDepositBase = ConstU128<1>;
+	type MetadataDepositPerByte = ConstU128<1>;
+	type ApprovalDeposit = ConstU128<1>;
+	type StringLimit = ConstU32<50>;
+	type Freezer = ();
+	type Extra = ();
+	type WeightInfo = ();
+}
+
+impl pallet_preimage::Config for Test {
+	type WeightInfo = ();
+	type RuntimeEvent = RuntimeEvent;
+	type Currency = Balances;
+	type ManagerOrigin = EnsureRoot<AccountId>;
+	type BaseDeposit = ConstU128<0>;
+	type ByteDeposit = ConstU128<0>;
+}
+
+parameter_types! {
+	pub MaximumSchedulerWeight: Weight = Weight::from_ref_time(1_000_000_000_000);
+}
+
+impl pallet_scheduler::Config for Test {
+	type RuntimeEvent = RuntimeEvent;
+	type RuntimeOrigin = RuntimeOrigin;
+	type PalletsOrigin = OriginCaller;
+	type RuntimeCall = RuntimeCall;
+	type MaximumWeight = MaximumSchedulerWeight;
+	type ScheduleOrigin = EnsureRoot<AccountId>;
+	type MaxScheduledPerBlock = ConstU32<50>;
+	type WeightInfo = ();
+	type OriginPrivilegeCmp = EqualPrivilegeOnly;
+	type Preimages = Preimage;
+}
+
+/// Randomness derived from the subject only, so minted token ids are the same on every run.
+pub struct TestRandomness;
+
+impl Randomness<H256, u64> for TestRandomness {
+	fn random(subject: &[u8]) -> (H256, u64) {
+		(BlakeTwo256::hash(subject), System::block_number())
+	}
+}
+
+impl pallet_nft_currency::Config for Test {
+	type RuntimeEvent = RuntimeEvent;
+	type AdminOrigin = EnsureRoot<AccountId>;
+	type Randomness = TestRandomness;
+	type AuthorityId = pallet_nft_currency::metadata::crypto::MetadataAuthId;
+	type VerifierOrigin = EnsureSigned<AccountId>;
+	type RecheckInterval = ConstU64<100>;
+	type MaxMetadataChecks = ConstU32<2>;
+	type WeightInfo = ();
+}
+
+impl frame_system::offchain::SigningTypes for Test {
+	type Public = MultiSigner;
+	type Signature = MultiSignature;
+}
+
+impl<C> frame_system::offchain::CreateSignedTransaction<C> for Test
+where
+	RuntimeCall: From<C>,
+{
+	fn create_transaction<A: frame_system::offchain::AppCrypto<MultiSigner, MultiSignature>>(
+		call: RuntimeCall,
+		_public: MultiSigner,
+		_account: AccountId,
+		nonce: u64,
+	) -> Option<(RuntimeCall, (u64, ()))> {
+		Some((call, (nonce, ())))
+	}
+}
+
+impl pallet_uniques::Config for Test {
+	type RuntimeEvent = RuntimeEvent;
+	type CollectionId = u32;
+	type ItemId = u32;
+	type Currency = Balances;
+	type ForceOrigin = EnsureRoot<AccountId>;
+	type CreateOrigin = AsEnsureOriginWithArg<EnsureSigned<AccountId>>;
+	type Locker = Renting;
+	type CollectionDeposit = ConstU128<0>;
+	type ItemDeposit = ConstU128<0>;
+	type MetadataDepositBase = ConstU128<0>;
+	type AttributeDepositBase = ConstU128<0>;
+	type DepositPerByte = ConstU128<0>;
+	type StringLimit = ConstU32<50>;
+	type KeyLimit = ConstU32<32>;
+	type ValueLimit = ConstU32<64>;
+	type WeightInfo = ();
+}
+
+parameter_types! {
+	pub FeeAccount: AccountId = AccountId::unchecked_from(H256::repeat_byte(0xfe));
+	pub const DefaultMarketplaceFee: Permill = Permill::from_percent(2);
+	pub const LateFee: Permill = Permill::from_percent(5);
+	pub const RentingPalletId: PalletId = PalletId(*b"py/rentg");
+}
+
+impl pallet_renting::Config for Test {
+	type RuntimeEvent = RuntimeEvent;
+	type RuntimeCall = RuntimeCall;
+	type PalletsOrigin = OriginCaller;
+	type Scheduler = Scheduler;
+	type Preimages = Preimage;
+	type Randomness = TestRandomness;
+	type TokenNFT = NftCurrency;
+	type Uniques = pallet_renting::UniquesItems<Test>;
+	type Signature = sp_core::ecdsa::Signature;
+	type PublicKey = sp_core::ecdsa::Public;
+	type Timestamp = Timestamp;
+	type BlockTime = ConstU64<BLOCK_TIME>;
+	type Currency = Balances;
+	type Assets = Assets;
+	type FeeDestination = FeeAccount;
+	type DefaultMarketplaceFee = DefaultMarketplaceFee;
+	type GracePeriod = ConstU64<30>;
+	type MaxPaymentRetries = ConstU32<3>;
+	type LateFee = LateFee;
+	type AdminOrigin = EnsureRoot<AccountId>;
+	type ArbiterOrigin = EnsureRoot<AccountId>;
+	type DisputeDeposit = ConstU64<DISPUTE_DEPOSIT>;
+	type PalletId = RentingPalletId;
+	type CleanupInterval = ConstU64<10>;
+	type MaxCleanup = ConstU32<16>;
+	type UnsignedPriority = ConstU64<{ u64::MAX / 2 }>;
+	type WeightInfo = ();
+}
+
+impl<C> frame_system::offchain::SendTransactionTypes<C> for Test
+where
+	RuntimeCall: From<C>,
+{
+	type OverarchingCall = RuntimeCall;
+	type Extrinsic = Extrinsic;
+}
+
+pub fn pair(seed: &str) -> sr25519::Pair {
+	sr25519::Pair::from_string(&format!("//{}", seed), None).expect("static seed is valid")
+}
+
+pub fn alice() -> sr25519::Pair {
+	pair("Alice")
+}
+
+pub fn bob() -> sr25519::Pair {
+	pair("Bob")
+}
+
+pub fn charlie() -> sr25519::Pair {
+	pair("Charlie")
+}
+
+pub fn account(pair: &sr25519::Pair) -> AccountId {
+	pair.public().into()
+}
+
+/// SS58 address of an account, as written in an order.
+pub fn address(pair: &sr25519::Pair) -> String {
+	account(pair).to_ss58check()
+}
+
+pub fn sign(pair: &sr25519::Pair, message: &[u8]) -> Vec<u8> {
+	pair.sign(message).0.to_vec()
+}
+
+/// Hex string of a token id, as written in an order.
+pub fn token_hex(token: &[u8]) -> String {
+	token.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Move to block `n`, advancing the clock by one block time per block and running the scheduler.
+pub fn run_to_block(n: u64) {
+	use frame_support::traits::{OnFinalize, OnInitialize};
+	while System::block_number() < n {
+		Scheduler::on_finalize(System::block_number());
+		System::set_block_number(System::block_number() + 1);
+		Timestamp::set_timestamp(GENESIS_TIME + (System::block_number() - 1) * BLOCK_TIME);
+		Scheduler::on_initialize(System::block_number());
+	}
+}
+
+// Build genesis storage according to the mock runtime.
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	let mut t = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap();
+	pallet_balances::GenesisConfig::<Test> {
+		balances: vec![
+			(account(&alice()), 1_000_000_000),
+			(account(&bob()), 1_000_000_000),
+			(account(&charlie()), 1_000_000_000),
+			(FeeAccount::get(), 1),
+		],
+	}
+	.assimilate_storage(&mut t)
+	.unwrap();
+	let mut ext = sp_io::TestExternalities::new(t);
+	// benchmarks sign their orders with keys of the keystore
+	ext.register_extension(KeystoreExt(Arc::new(KeyStore::new())));
+	ext.execute_with(|| {
+		System::set_block_number(1);
+		Timestamp::set_timestamp(GENESIS_TIME);
+	});
+	ext
+}
@@ -3,6 +3,66 @@ use frame_support::pallet_prelude::*;
 use frame_support::storage::StorageDecodeLength;
 use sp_std::{vec, vec::Vec};
 
+/// When the first instalment of a periodic plan is paid.
+#[derive(Clone, Copy, Encode, Decode, PartialEq, Eq, TypeInfo, Debug)]
+pub enum FirstPayment {
+	/// Paid when the rental starts, then at the start of every following period.
+	Upfront,
+	/// Paid at the end of every period, the last one when the rental ends.
+	Deferred,
+}
+
+/// How the rent of an order is billed. For periodic plans `fee` is the price of one period.
+#[derive(Clone, Copy, Encode, Decode, PartialEq, Eq, TypeInfo, Debug)]
+pub enum BillingPlan {
+	/// `fee` is the price of the whole rental, paid when it starts.
+	Upfront,
+	/// `fee` is paid every `period` blocks.
+	EveryBlocks { period: u32, first: FirstPayment },
+	/// `fee` is paid every `period` seconds.
+	EverySeconds { period: u64, first: FirstPayment },
+}
+
+impl BillingPlan {
+	/// Whether an instalment is due when the rental starts.
+	pub fn pays_upfront(&self) -> bool {
+		match self {
+			BillingPlan::Upfront => true,
+			BillingPlan::EveryBlocks { first, .. } | BillingPlan::EverySeconds { first, .. } => {
+				*first == FirstPayment::Upfront
+			},
+		}
+	}
+}
+
+impl Default for BillingPlan {
+	fn default() -> Self {
+		BillingPlan::Upfront
+	}
+}
+
+/// Pallet keeping the token of an order.
+#[derive(Clone, Copy, Encode, Decode, PartialEq, Eq, TypeInfo, Debug)]
+pub enum Registry {
+	/// `T::TokenNFT`, the default.
+	NftCurrency,
+	/// `T::Uniques`, the token id is the encoded `(collection, item)` pair.
+	Uniques,
+}
+
+impl Default for Registry {
+	fn default() -> Self {
+		Registry::NftCurrency
+	}
+}
+
+/// Credential a borrower must hold to match an order.
+#[derive(Clone, Copy, Encode, Decode, PartialEq, Eq, TypeInfo, Debug)]
+pub enum Credential {
+	/// The borrower is on the allowlist of the lender.
+	Allowlist,
+}
+
 #[derive(Clone, Encode, Decode, PartialEq, TypeInfo, Debug)]
 #[scale_info(skip_type_params(T))]
 pub struct Order {
@@ -12,7 +72,11 @@ pub struct Order {
 	pub(crate) fee: u64,
 	pub(crate) token: Vec<u8>,
 	pub(crate) due_date: u64,
-	pub(crate) paid_type: u8, // at once :0, per day: 1, per week:2
+	pub(crate) billing: BillingPlan,
+	pub(crate) asset_id: Option<u32>, // None: native currency
+	pub(crate) min_reputation: u32,   // score the borrower needs without failures, 0: anyone
+	pub(crate) credential: Option<Credential>,
+	pub(crate) registry: Registry,
 }
 
 impl Order {
@@ -23,9 +87,29 @@ impl Order {
 			fee: 0,
 			token: vec![],
 			due_date: 0,
-			paid_type: 0,
+			billing: BillingPlan::Upfront,
+			asset_id: None,
+			min_reputation: 0,
+			credential: None,
+			registry: Registry::NftCurrency,
 		}
 	}
+
+	pub fn lender(&self) -> [u8; 32] {
+		self.lender
+	}
+
+	pub fn fee(&self) -> u64 {
+		self.fee
+	}
+
+	pub fn due_date(&self) -> u64 {
+		self.due_date
+	}
+
+	pub fn asset_id(&self) -> Option<u32> {
+		self.asset_id
+	}
 }
 
 impl Default for Order {
@@ -33,3 +117,10 @@ impl Default for Order {
 		Self::new()
 	}
 }
+
+#[derive(Clone, Encode, Decode, PartialEq, TypeInfo, Debug, Default)]
+pub struct OverduePayment {
+	pub(crate) amount: u64,   // instalments not paid yet
+	pub(crate) late_fee: u64, // accumulated late fee owed to the lender
+	pub(crate) attempts: u32,
+}
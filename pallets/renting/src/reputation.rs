@@ -0,0 +1,40 @@
+use frame_support::pallet_prelude::*;
+
+/// What an account did as lender or borrower in past rentals.
+///
+/// The record only tells accounts with a track record apart: a new account and one whose failures
+/// outnumber its completed rentals both score 0, and two accounts renting to each other can build
+/// a score at the cost of the fees. A minimum reputation therefore keeps out accounts that failed
+/// before, not accounts created to rent once.
+#[derive(Clone, Encode, Decode, PartialEq, Eq, TypeInfo, Debug, Default, MaxEncodedLen)]
+pub struct Reputation {
+	/// Rentals with another account that ran until their due date. A borrower only counts those it
+	/// paid in full.
+	pub completed: u32,
+	/// Instalments the account failed to pay when they fell due.
+	pub missed_repayments: u32,
+	/// Rentals the account stopped before their due date.
+	pub early_stops: u32,
+	/// Disputes the arbiter settled for the other party.
+	pub disputes_lost: u32,
+}
+
+impl Reputation {
+	/// Completed rentals minus every recorded failure.
+	pub fn score(&self) -> u32 {
+		self.completed
+			.saturating_sub(self.missed_repayments)
+			.saturating_sub(self.early_stops)
+			.saturating_sub(self.disputes_lost)
+	}
+
+	/// Whether the account may match an order asking a `min_reputation` score. Completed rentals
+	/// do not make up for a missed instalment or a lost dispute, an account with one of them only
+	/// matches orders open to anyone.
+	pub fn qualifies_for(&self, min_reputation: u32) -> bool {
+		min_reputation == 0
+			|| (self.missed_repayments == 0
+				&& self.disputes_lost == 0
+				&& self.score() >= min_reputation)
+	}
+}
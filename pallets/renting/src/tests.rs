@@ -0,0 +1,1467 @@
+use crate::{
+	convert::*, migration::v1, mock::*, BillingPlan, Borrowers, CancelOrder, DisputeAction,
+	DisputeParty, DisputeResolution, Error, Event, FirstPayment, Order, OverduePayment, Registry,
+	RentalTask, Reputation, Reputations, StaleEntry, UsageRight, UsageRightTokens,
+};
+use codec::{Decode, Encode};
+use frame_support::{
+	assert_noop, assert_ok,
+	dispatch::DispatchResult,
+	pallet_prelude::{InvalidTransaction, TransactionSource, ValidateUnsigned},
+	traits::{
+		schedule::v3::Named, tokens::nonfungibles::Inspect, Currency, Get, GetStorageVersion,
+		OffchainWorker, OnRuntimeUpgrade, StorageVersion,
+	},
+};
+use pallet_nft_currency::NonFungibleToken;
+use proptest::prelude::*;
+use sp_core::{
+	offchain::{testing, OffchainWorkerExt, TransactionPoolExt},
+	sr25519,
+};
+use sp_runtime::{DispatchError, Permill};
+
+// Keys an order understands, plus one it ignores.
+const ORDER_KEYS: [&str; 13] = [
+	"lender",
+	"borrower",
+	"fee",
+	"token",
+	"due_date",
+	"billing",
+	"period",
+	"deferred",
+	"asset_id",
+	"min_reputation",
+	"credential",
+	"registry",
+	"unknown",
+];
+
+/// Any JSON value an order field could be set to, well-formed or not.
+fn json_value() -> impl Strategy<Value = String> {
+	prop_oneof![
+		any::<u64>().prop_map(|n| n.to_string()),
+		any::<i64>().prop_map(|n| n.to_string()),
+		any::<f64>().prop_map(|n| n.to_string()),
+		any::<bool>().prop_map(|b| b.to_string()),
+		Just("null".to_string()),
+		Just("[]".to_string()),
+		Just("{}".to_string()),
+		Just(format!("\"{}\"", address(&alice()))),
+		Just(format!("\"{}\"", address(&bob()))),
+		prop::sample::select(vec!["upfront", "blocks", "seconds", ""])
+			.prop_map(|s| format!("\"{}\"", s)),
+		"[0-9a-fA-Fx]{0,80}".prop_map(|s| format!("\"{}\"", s)),
+		"\\PC{0,40}".prop_map(|s| format!("{:?}", s)),
+	]
+}
+
+/// A JSON object made of order keys and arbitrary values.
+fn json_order() -> impl Strategy<Value = Vec<u8>> {
+	prop::collection::vec((prop::sample::select(ORDER_KEYS.to_vec()), json_value()), 0..12)
+		.prop_map(|fields| {
+			let body = fields
+				.iter()
+				.map(|(key, value)| format!("\"{}\":{}", key, value))
+				.collect::<Vec<_>>()
+				.join(",");
+			format!("{{{}}}", body).into_bytes()
+		})
+}
+
+/// Either a JSON order or raw bytes.
+fn message() -> impl Strategy<Value = Vec<u8>> {
+	prop_oneof![json_order(), prop::collection::vec(any::<u8>(), 0..256)]
+}
+
+proptest! {
+	#[test]
+	fn create_rental_never_panics(
+		message_left in message(),
+		message_right in message(),
+		signature in prop::collection::vec(any::<u8>(), 0..80),
+		caller_is_lender in any::<bool>(),
+		signed in any::<bool>(),
+	) {
+		new_test_ext().execute_with(|| {
+			let (lender, borrower) = (alice(), bob());
+			assert_ok!(NftCurrency::mint_to(
+				RuntimeOrigin::signed(account(&lender)),
+				account(&lender),
+				b"uri".to_vec()
+			));
+			let (signature_left, signature_right) = if signed {
+				(sign(&lender, &message_left), sign(&borrower, &message_right))
+			} else {
+				(signature.clone(), signature)
+			};
+			let caller = if caller_is_lender { &lender } else { &borrower };
+			let _ = Renting::create_rental(
+				RuntimeOrigin::signed(account(caller)),
+				account(&lender),
+				account(&borrower),
+				message_left,
+				signature_left,
+				message_right,
+				signature_right,
+			);
+		});
+	}
+
+	#[test]
+	fn cancel_offer_never_panics(message in message(), is_lender in any::<bool>()) {
+		new_test_ext().execute_with(|| {
+			let _ = Renting::cancel_offer(RuntimeOrigin::signed(account(&alice())), message, is_lender);
+		});
+	}
+
+	#[test]
+	fn account_conversions_never_panic(address in "\\PC{0,64}", bytes in any::<[u8; 32]>()) {
+		let _ = convert_string_to_accountid::<AccountId>(&address);
+		prop_assert!(convert_bytes_to_accountid::<AccountId>(bytes).is_some());
+	}
+
+	#[test]
+	fn hex_tokens_round_trip(token in prop::collection::vec(any::<u8>(), 0..64)) {
+		prop_assert_eq!(hex_string_to_vec(token_hex(&token)), Some(token));
+	}
+
+	#[test]
+	fn hex_string_to_vec_never_panics(hex in "\\PC{0,80}") {
+		let _ = hex_string_to_vec(hex);
+	}
+}
+
+const FEE: u64 = 1_000;
+
+/// Due date `blocks` blocks after the genesis block, in seconds.
+fn due_in(blocks: u64) -> u64 {
+	(GENESIS_TIME + blocks * BLOCK_TIME) / 1000
+}
+
+/// Order JSON of `lender`, or of `borrower` when set, with the billing fields in `billing`.
+fn order(
+	lender: &sr25519::Pair,
+	borrower: Option<&sr25519::Pair>,
+	token: &[u8],
+	fee: u64,
+	due_date: u64,
+	billing: &str,
+) -> Vec<u8> {
+	let borrower = borrower
+		.map(|borrower| format!("\"borrower\":\"{}\",", address(borrower)))
+		.unwrap_or_default();
+	format!(
+		"{{\"lender\":\"{}\",{}\"fee\":{},\"token\":\"{}\",\"due_date\":{}{}}}",
+		address(lender),
+		borrower,
+		fee,
+		token_hex(token),
+		due_date,
+		billing
+	)
+	.into_bytes()
+}
+
+/// Token minted by Alice.
+fn mint_token() -> Vec<u8> {
+	assert_ok!(NftCurrency::mint_to(
+		RuntimeOrigin::signed(account(&alice())),
+		account(&alice()),
+		b"uri".to_vec()
+	));
+	NftCurrency::list_owned(account(&alice())).last().cloned().unwrap()
+}
+
+/// Bob rents `token` from Alice until `due_date`, both having signed the same terms.
+fn rent(token: &[u8], due_date: u64, billing: &str) -> DispatchResult {
+	let message_left = order(&alice(), None, token, FEE, due_date, billing);
+	let message_right = order(&alice(), Some(&bob()), token, FEE, due_date, billing);
+	Renting::create_rental(
+		RuntimeOrigin::signed(account(&bob())),
+		account(&alice()),
+		account(&bob()),
+		message_left.clone(),
+		sign(&alice(), &message_left),
+		message_right.clone(),
+		sign(&bob(), &message_right),
+	)
+}
+
+fn custodian(token: &[u8]) -> Option<AccountId> {
+	<NftCurrency as NonFungibleToken<AccountId>>::custodian_of_token(token.to_vec())
+}
+
+fn balance(pair: &sr25519::Pair) -> u128 {
+	Balances::free_balance(account(pair))
+}
+
+/// Amount the lender receives out of `amount` once the marketplace cut is taken.
+fn net(amount: u64) -> u128 {
+	(amount - DefaultMarketplaceFee::get().mul_floor(amount)) as u128
+}
+
+fn renting_events() -> Vec<Event<Test>> {
+	System::events()
+		.into_iter()
+		.filter_map(|record| match record.event {
+			RuntimeEvent::Renting(event) => Some(event),
+			_ => None,
+		})
+		.collect()
+}
+
+#[test]
+fn create_rental_with_signed_orders() {
+	new_test_ext().execute_with(|| {
+		let token = mint_token();
+		let alice_before = balance(&alice());
+		assert_ok!(rent(&token, due_in(10), ""));
+
+		let rental = Renting::borrowers(account(&bob()), token.clone());
+		assert_eq!(rental.lender, account_to_bytes(&account(&alice())).unwrap());
+		assert_eq!(rental.borrower, account_to_bytes(&account(&bob())).unwrap());
+		assert_eq!(rental.due_date, due_in(10));
+		assert_eq!(custodian(&token), Some(account(&bob())));
+		assert_eq!(balance(&alice()), alice_before + net(FEE));
+		System::assert_has_event(
+			Event::MatchOrder(account(&alice()), account(&bob()), token).into(),
+		);
+	});
+}
+
+#[test]
+fn marketplace_cut_below_the_asset_minimum_is_left_to_the_lender() {
+	new_test_ext().execute_with(|| {
+		let token = mint_token();
+		assert_ok!(Assets::force_create(RuntimeOrigin::root(), 1, account(&charlie()), true, 100));
+		assert_ok!(Assets::mint(
+			RuntimeOrigin::signed(account(&charlie())),
+			1,
+			account(&bob()),
+			10 * FEE as u128
+		));
+		// the 2% cut of the fee is below the minimum balance of the asset
+		assert_ok!(rent(&token, due_in(10), ",\"asset_id\":1"));
+		assert_eq!(Assets::balance(1, account(&alice())), FEE as u128);
+		assert_eq!(Assets::balance(1, FeeAccount::get()), 0);
+		assert!(!renting_events().iter().any(|e| matches!(e, Event::MarketplaceFeeTaken(..))));
+	});
+}
+
+#[test]
+fn lender_can_submit_the_match() {
+	new_test_ext().execute_with(|| {
+		let token = mint_token();
+		let message_left = order(&alice(), None, &token, FEE, due_in(10), "");
+		let message_right = order(&alice(), Some(&bob()), &token, FEE, due_in(10), "");
+		// only the counterparty has to sign when the lender submits
+		assert_ok!(Renting::create_rental(
+			RuntimeOrigin::signed(account(&alice())),
+			account(&alice()),
+			account(&bob()),
+			message_left,
+			vec![],
+			message_right.clone(),
+			sign(&bob(), &message_right),
+		));
+		assert_eq!(custodian(&token), Some(account(&bob())));
+	});
+}
+
+#[test]
+fn create_rental_rejects_invalid_signatures() {
+	new_test_ext().execute_with(|| {
+		let token = mint_token();
+		let message_left = order(&alice(), None, &token, FEE, due_in(10), "");
+		let message_right = order(&alice(), Some(&bob()), &token, FEE, due_in(10), "");
+		assert_noop!(
+			Renting::create_rental(
+				RuntimeOrigin::signed(account(&bob())),
+				account(&alice()),
+				account(&bob()),
+				message_left.clone(),
+				sign(&charlie(), &message_left),
+				message_right.clone(),
+				vec![],
+			),
+			Error::<Test>::SignatureVerifyError2
+		);
+		assert_noop!(
+			Renting::create_rental(
+				RuntimeOrigin::signed(account(&bob())),
+				account(&alice()),
+				account(&bob()),
+				message_left,
+				vec![1, 2, 3],
+				message_right,
+				vec![],
+			),
+			Error::<Test>::SignatureVerifyError1
+		);
+	});
+}
+
+#[test]
+fn create_rental_rejects_third_parties() {
+	new_test_ext().execute_with(|| {
+		let token = mint_token();
+		let message_left = order(&alice(), None, &token, FEE, due_in(10), "");
+		let message_right = order(&alice(), Some(&bob()), &token, FEE, due_in(10), "");
+		assert_noop!(
+			Renting::create_rental(
+				RuntimeOrigin::signed(account(&charlie())),
+				account(&alice()),
+				account(&bob()),
+				message_left.clone(),
+				sign(&alice(), &message_left),
+				message_right.clone(),
+				sign(&bob(), &message_right),
+			),
+			DispatchError::CannotLookup
+		);
+	});
+}
+
+#[test]
+fn create_rental_rejects_orders_that_do_not_match() {
+	new_test_ext().execute_with(|| {
+		let token = mint_token();
+		let other = mint_token();
+		let message_left = order(&alice(), None, &token, FEE, due_in(10), "");
+		let rent_with = |message_right: Vec<u8>| {
+			Renting::create_rental(
+				RuntimeOrigin::signed(account(&bob())),
+				account(&alice()),
+				account(&bob()),
+				message_left.clone(),
+				sign(&alice(), &message_left),
+				message_right,
+				vec![],
+			)
+		};
+		assert_noop!(
+			rent_with(order(&alice(), Some(&bob()), &other, FEE, due_in(10), "")),
+			Error::<Test>::NotMatchToken
+		);
+		assert_noop!(
+			rent_with(order(&alice(), Some(&bob()), &token, FEE - 1, due_in(10), "")),
+			Error::<Test>::NotEnoughFee
+		);
+		assert_noop!(
+			rent_with(order(&alice(), Some(&bob()), &token, FEE, due_in(11), "")),
+			Error::<Test>::TimeOver
+		);
+		assert_noop!(
+			rent_with(order(
+				&alice(),
+				Some(&bob()),
+				&token,
+				FEE,
+				due_in(10),
+				",\"billing\":\"blocks\",\"period\":2"
+			)),
+			Error::<Test>::NotMatchBillingPlan
+		);
+		assert_noop!(
+			rent_with(order(&alice(), Some(&charlie()), &token, FEE, due_in(10), "")),
+			Error::<Test>::NotMatchBorrower
+		);
+	});
+}
+
+#[test]
+fn cancelled_offers_cannot_be_matched() {
+	new_test_ext().execute_with(|| {
+		let token = mint_token();
+		let message_left = order(&alice(), None, &token, FEE, due_in(10), "");
+		assert_ok!(Renting::cancel_offer(
+			RuntimeOrigin::signed(account(&alice())),
+			message_left,
+			true
+		));
+		assert_noop!(rent(&token, due_in(10), ""), Error::<Test>::AlreadyCanceled);
+		assert_eq!(custodian(&token), Some(account(&alice())));
+	});
+}
+
+#[test]
+fn only_the_author_cancels_an_offer() {
+	new_test_ext().execute_with(|| {
+		let token = mint_token();
+		let message_left = order(&alice(), None, &token, FEE, due_in(10), "");
+		assert_noop!(
+			Renting::cancel_offer(RuntimeOrigin::signed(account(&bob())), message_left, true),
+			Error::<Test>::NotMatchLender
+		);
+	});
+}
+
+#[test]
+fn upfront_rental_is_paid_once() {
+	new_test_ext().execute_with(|| {
+		let token = mint_token();
+		let alice_before = balance(&alice());
+		assert_ok!(rent(&token, due_in(10), ",\"billing\":\"upfront\""));
+		run_to_block(11);
+		assert_eq!(balance(&alice()), alice_before + net(FEE));
+	});
+}
+
+#[test]
+fn every_blocks_rental_pays_at_the_start_of_each_period() {
+	new_test_ext().execute_with(|| {
+		let token = mint_token();
+		let alice_before = balance(&alice());
+		assert_ok!(rent(&token, due_in(6), ",\"billing\":\"blocks\",\"period\":2"));
+		assert_eq!(balance(&alice()), alice_before + net(FEE));
+		run_to_block(3);
+		assert_eq!(balance(&alice()), alice_before + 2 * net(FEE));
+		run_to_block(7);
+		// instalments at blocks 1, 3 and 5, none at the due block
+		assert_eq!(balance(&alice()), alice_before + 3 * net(FEE));
+	});
+}
+
+#[test]
+fn deferred_rental_pays_at_the_end_of_each_period() {
+	new_test_ext().execute_with(|| {
+		let token = mint_token();
+		let alice_before = balance(&alice());
+		let billing = ",\"billing\":\"blocks\",\"period\":2,\"deferred\":true";
+		assert_ok!(rent(&token, due_in(6), billing));
+		assert_eq!(balance(&alice()), alice_before);
+		run_to_block(6);
+		assert_eq!(balance(&alice()), alice_before + 2 * net(FEE));
+		run_to_block(7);
+		// the last instalment is collected before the asset goes back
+		assert_eq!(balance(&alice()), alice_before + 3 * net(FEE));
+		assert_eq!(custodian(&token), Some(account(&alice())));
+	});
+}
+
+#[test]
+fn every_seconds_rental_converts_the_period_to_blocks() {
+	new_test_ext().execute_with(|| {
+		let token = mint_token();
+		let alice_before = balance(&alice());
+		let period = 2 * BLOCK_TIME / 1000;
+		let billing = format!(",\"billing\":\"seconds\",\"period\":{}", period);
+		assert_ok!(rent(&token, due_in(6), &billing));
+		run_to_block(7);
+		assert_eq!(balance(&alice()), alice_before + 3 * net(FEE));
+	});
+}
+
+#[test]
+fn rejects_unknown_billing_plan() {
+	new_test_ext().execute_with(|| {
+		let token = mint_token();
+		assert_noop!(
+			rent(&token, due_in(6), ",\"billing\":\"weekly\""),
+			Error::<Test>::NotPaidType
+		);
+		assert_noop!(
+			rent(&token, due_in(6), ",\"billing\":\"blocks\",\"period\":0"),
+			Error::<Test>::NotPaidType
+		);
+	});
+}
+
+#[test]
+fn missed_repayment_is_retried_with_late_fee() {
+	new_test_ext().execute_with(|| {
+		let token = mint_token();
+		let alice_before = balance(&alice());
+		assert_ok!(rent(&token, due_in(100), ",\"billing\":\"blocks\",\"period\":2"));
+		Balances::make_free_balance_be(&account(&bob()), 1);
+
+		run_to_block(3);
+		let late_fee = LateFee::get().mul_floor(FEE);
+		assert_eq!(
+			Renting::overdue(account(&bob()), token.clone()),
+			Some(OverduePayment { amount: FEE, late_fee, attempts: 1 })
+		);
+		System::assert_has_event(
+			Event::PaymentOverdue(account(&bob()), account(&alice()), token.clone(), 1, late_fee)
+				.into(),
+		);
+
+		// the borrower is funded again before the retry, ten blocks later
+		Balances::make_free_balance_be(&account(&bob()), 1_000_000_000);
+		run_to_block(13);
+		assert_eq!(Renting::overdue(account(&bob()), token.clone()), None);
+		// the instalments of blocks 1 to 13 plus one late fee
+		assert_eq!(balance(&alice()), alice_before + net(6 * FEE) + net(FEE) + late_fee as u128);
+		assert_eq!(custodian(&token), Some(account(&bob())));
+	});
+}
+
+#[test]
+fn asset_is_taken_back_once_retries_are_exhausted() {
+	new_test_ext().execute_with(|| {
+		let token = mint_token();
+		assert_ok!(rent(&token, due_in(100), ",\"billing\":\"blocks\",\"period\":2"));
+		Balances::make_free_balance_be(&account(&bob()), 1);
+
+		// retried at blocks 13, 23 and 33, within the grace period of 30 blocks
+		run_to_block(32);
+		assert_eq!(Renting::overdue(account(&bob()), token.clone()).map(|o| o.attempts), Some(3));
+		run_to_block(33);
+		assert!(Borrowers::<Test>::try_get(account(&bob()), token.clone()).is_err());
+		assert_eq!(Renting::overdue(account(&bob()), token.clone()), None);
+		assert_eq!(custodian(&token), Some(account(&alice())));
+		System::assert_has_event(
+			Event::ReturnAsset(account(&bob()), account(&alice()), token).into(),
+		);
+	});
+}
+
+#[test]
+fn overdue_instalments_are_settled_when_the_asset_is_returned() {
+	new_test_ext().execute_with(|| {
+		let token = mint_token();
+		let alice_before = balance(&alice());
+		assert_ok!(rent(&token, due_in(10), ",\"billing\":\"blocks\",\"period\":2"));
+		Balances::make_free_balance_be(&account(&bob()), 1);
+		run_to_block(4);
+		assert_noop!(
+			Renting::stop_renting(RuntimeOrigin::signed(account(&bob())), token.clone()),
+			Error::<Test>::PaymentOverdue
+		);
+
+		// the instalments of blocks 3 to 9 are still overdue at the due block, before the retry
+		Balances::make_free_balance_be(&account(&bob()), 1_000_000_000);
+		run_to_block(11);
+		assert_eq!(custodian(&token), Some(account(&alice())));
+		assert_eq!(Renting::overdue(account(&bob()), token.clone()), None);
+		let late_fee = LateFee::get().mul_floor(FEE) as u128;
+		assert_eq!(balance(&alice()), alice_before + net(FEE) + net(4 * FEE) + late_fee);
+		assert_eq!(Renting::reputation(account(&bob())).completed, 1);
+	});
+}
+
+#[test]
+fn borrower_overdue_at_the_due_block_defaults() {
+	new_test_ext().execute_with(|| {
+		let token = mint_token();
+		assert_ok!(rent(&token, due_in(10), ",\"billing\":\"blocks\",\"period\":2"));
+		Balances::make_free_balance_be(&account(&bob()), 1);
+		run_to_block(11);
+		assert_eq!(custodian(&token), Some(account(&alice())));
+		assert_eq!(Renting::overdue(account(&bob()), token.clone()), None);
+		assert_eq!(
+			Renting::reputation(account(&bob())),
+			Reputation { missed_repayments: 4, ..Default::default() }
+		);
+		assert_eq!(Renting::reputation(account(&alice())).completed, 1);
+	});
+}
+
+#[test]
+fn failed_last_deferred_instalment_is_not_a_completed_rental() {
+	new_test_ext().execute_with(|| {
+		let token = mint_token();
+		let alice_before = balance(&alice());
+		let billing = ",\"billing\":\"blocks\",\"period\":2,\"deferred\":true";
+		assert_ok!(rent(&token, due_in(6), billing));
+		run_to_block(6);
+		Balances::make_free_balance_be(&account(&bob()), 1);
+
+		// the last instalment and the return fall due in block 7, the return tries it again
+		run_to_block(7);
+		assert_eq!(custodian(&token), Some(account(&alice())));
+		assert_eq!(balance(&alice()), alice_before + 2 * net(FEE));
+		assert_eq!(
+			Renting::reputation(account(&bob())),
+			Reputation { missed_repayments: 1, ..Default::default() }
+		);
+		assert_eq!(Renting::reputation(account(&alice())).completed, 1);
+		// nothing is retried once the asset is back
+		run_to_block(40);
+		assert_eq!(balance(&alice()), alice_before + 2 * net(FEE));
+	});
+}
+
+#[test]
+fn asset_is_returned_at_the_due_block() {
+	new_test_ext().execute_with(|| {
+		let token = mint_token();
+		assert_ok!(rent(&token, due_in(10), ""));
+		run_to_block(10);
+		assert_eq!(custodian(&token), Some(account(&bob())));
+		run_to_block(11);
+		assert_eq!(custodian(&token), Some(account(&alice())));
+		assert!(Borrowers::<Test>::try_get(account(&bob()), token.clone()).is_err());
+		System::assert_has_event(
+			Event::ReturnAsset(account(&bob()), account(&alice()), token).into(),
+		);
+	});
+}
+
+#[test]
+fn return_moves_to_the_next_block_when_the_agenda_is_full() {
+	new_test_ext().execute_with(|| {
+		let remark = RuntimeCall::System(frame_system::Call::remark { remark: vec![] });
+		for _ in 0..<Test as pallet_scheduler::Config>::MaxScheduledPerBlock::get() {
+			assert_ok!(Scheduler::schedule(
+				RuntimeOrigin::root(),
+				11,
+				None,
+				0,
+				Box::new(remark.clone())
+			));
+		}
+		let token = mint_token();
+		assert_ok!(rent(&token, due_in(10), ""));
+		run_to_block(11);
+		assert_eq!(custodian(&token), Some(account(&bob())));
+		run_to_block(12);
+		assert_eq!(custodian(&token), Some(account(&alice())));
+	});
+}
+
+#[test]
+fn anyone_returns_the_asset_once_the_due_date_is_over() {
+	new_test_ext().execute_with(|| {
+		let token = mint_token();
+		assert_ok!(rent(&token, due_in(10), ""));
+		assert_noop!(
+			Renting::return_asset(
+				RuntimeOrigin::signed(account(&charlie())),
+				account(&bob()),
+				token.clone()
+			),
+			Error::<Test>::NotDue
+		);
+
+		// blocks are slow: the due date is over long before the due block
+		Timestamp::set_timestamp(due_in(10) * 1000);
+		assert_ok!(Renting::return_asset(
+			RuntimeOrigin::signed(account(&charlie())),
+			account(&bob()),
+			token.clone()
+		));
+		assert_eq!(custodian(&token), Some(account(&alice())));
+		System::assert_has_event(
+			Event::ReturnAsset(account(&bob()), account(&alice()), token.clone()).into(),
+		);
+		assert_noop!(
+			Renting::return_asset(
+				RuntimeOrigin::signed(account(&charlie())),
+				account(&bob()),
+				token
+			),
+			Error::<Test>::NoneExist
+		);
+	});
+}
+
+#[test]
+fn stop_renting_returns_the_asset_early() {
+	new_test_ext().execute_with(|| {
+		let token = mint_token();
+		assert_ok!(rent(&token, due_in(10), ",\"billing\":\"blocks\",\"period\":2"));
+		assert_noop!(
+			Renting::stop_renting(RuntimeOrigin::signed(account(&charlie())), token.clone()),
+			Error::<Test>::NoneExist
+		);
+		assert_ok!(Renting::stop_renting(RuntimeOrigin::signed(account(&bob())), token.clone()));
+		assert_eq!(custodian(&token), Some(account(&alice())));
+
+		// nothing is collected nor returned once the rental is over
+		let alice_before = balance(&alice());
+		run_to_block(20);
+		assert_eq!(balance(&alice()), alice_before);
+		assert!(!renting_events().iter().any(|event| matches!(event, Event::ReturnAsset(..))));
+	});
+}
+
+/// Ruling of the arbiter sending the whole escrow to the lender.
+fn ruling(in_favour_of: DisputeParty, action: DisputeAction) -> DisputeResolution {
+	DisputeResolution { in_favour_of, lender_share: Permill::one(), action }
+}
+
+#[test]
+fn open_dispute_freezes_the_rental() {
+	new_test_ext().execute_with(|| {
+		let token = mint_token();
+		assert_ok!(rent(&token, due_in(10), ",\"billing\":\"blocks\",\"period\":2"));
+		let alice_before = balance(&alice());
+		assert_ok!(Renting::open_dispute(
+			RuntimeOrigin::signed(account(&alice())),
+			Registry::NftCurrency,
+			token.clone(),
+			[1; 32]
+		));
+		System::assert_has_event(
+			Event::DisputeOpened(account(&bob()), token.clone(), account(&alice()), [1; 32]).into(),
+		);
+		assert_noop!(
+			Renting::stop_renting(RuntimeOrigin::signed(account(&bob())), token.clone()),
+			Error::<Test>::Disputed
+		);
+
+		// the instalments of blocks 3 to 9 are held in escrow and the asset is not returned
+		run_to_block(12);
+		assert_eq!(balance(&alice()), alice_before - DISPUTE_DEPOSIT as u128);
+		assert_eq!(Renting::disputes(account(&bob()), token.clone()).unwrap().escrow, 4 * 980);
+		assert_eq!(custodian(&token), Some(account(&bob())));
+	});
+}
+
+#[test]
+fn resolve_dispute_splits_the_escrow() {
+	new_test_ext().execute_with(|| {
+		let token = mint_token();
+		assert_ok!(rent(&token, due_in(10), ",\"billing\":\"blocks\",\"period\":2"));
+		assert_ok!(Renting::open_dispute(
+			RuntimeOrigin::signed(account(&alice())),
+			Registry::NftCurrency,
+			token.clone(),
+			[1; 32]
+		));
+		let alice_before = balance(&alice());
+		let bob_before = balance(&bob());
+		run_to_block(12);
+
+		let resolution = DisputeResolution {
+			in_favour_of: DisputeParty::Borrower,
+			lender_share: Permill::from_percent(50),
+			action: DisputeAction::Continue,
+		};
+		assert_ok!(Renting::resolve_dispute(
+			RuntimeOrigin::root(),
+			account(&bob()),
+			token.clone(),
+			resolution
+		));
+		let escrow = 4 * net(FEE);
+		assert_eq!(balance(&alice()), alice_before + escrow / 2);
+		// the lender opened the dispute and lost it, the deposit goes to the borrower
+		assert_eq!(
+			balance(&bob()),
+			bob_before - 4 * FEE as u128 + escrow / 2 + DISPUTE_DEPOSIT as u128
+		);
+		assert_eq!(Renting::disputes(account(&bob()), token.clone()), None);
+		let recorded = Renting::rulings(account(&bob()), token.clone()).unwrap();
+		assert_eq!(recorded.resolution, resolution);
+		assert_eq!(recorded.escrow, 4 * 980);
+
+		// the due date is past, the asset goes back on the next block
+		run_to_block(13);
+		assert_eq!(custodian(&token), Some(account(&alice())));
+	});
+}
+
+#[test]
+fn resolve_dispute_pays_out_an_asset_escrow_below_the_minimum_balance() {
+	new_test_ext().execute_with(|| {
+		let token = mint_token();
+		assert_ok!(Assets::force_create(RuntimeOrigin::root(), 1, account(&charlie()), true, 100));
+		assert_ok!(Assets::mint(
+			RuntimeOrigin::signed(account(&charlie())),
+			1,
+			account(&bob()),
+			10 * FEE as u128
+		));
+		assert_ok!(rent(&token, due_in(10), ",\"asset_id\":1,\"billing\":\"blocks\",\"period\":2"));
+		assert_ok!(Renting::open_dispute(
+			RuntimeOrigin::signed(account(&alice())),
+			Registry::NftCurrency,
+			token.clone(),
+			[1; 32]
+		));
+		// the instalments of blocks 3 and 5 are escrowed, the cut is below the asset minimum
+		run_to_block(6);
+		let bob_before = balance(&bob());
+
+		// the borrower share of 60 cannot stay in the escrow on its own, it goes to the lender
+		let resolution = DisputeResolution {
+			in_favour_of: DisputeParty::Borrower,
+			lender_share: Permill::from_percent(97),
+			action: DisputeAction::Continue,
+		};
+		assert_ok!(Renting::resolve_dispute(
+			RuntimeOrigin::root(),
+			account(&bob()),
+			token.clone(),
+			resolution
+		));
+		assert_eq!(Assets::balance(1, account(&alice())), 3 * FEE as u128);
+		assert_eq!(Assets::balance(1, account(&bob())), 7 * FEE as u128);
+		assert_eq!(balance(&bob()), bob_before + DISPUTE_DEPOSIT as u128);
+		assert_eq!(Renting::disputes(account(&bob()), token.clone()), None);
+	});
+}
+
+#[test]
+fn resolve_dispute_pays_an_asset_share_the_loser_cannot_receive_to_the_winner() {
+	new_test_ext().execute_with(|| {
+		let token = mint_token();
+		assert_ok!(Assets::force_create(RuntimeOrigin::root(), 1, account(&charlie()), true, 100));
+		assert_ok!(Assets::mint(
+			RuntimeOrigin::signed(account(&charlie())),
+			1,
+			account(&bob()),
+			10 * FEE as u128
+		));
+		let billing = ",\"asset_id\":1,\"billing\":\"blocks\",\"period\":2,\"deferred\":true";
+		assert_ok!(rent(&token, due_in(10), billing));
+		assert_ok!(Renting::open_dispute(
+			RuntimeOrigin::signed(account(&bob())),
+			Registry::NftCurrency,
+			token.clone(),
+			[1; 32]
+		));
+		run_to_block(6);
+
+		// the lender holds none of the asset and cannot be paid a share of 20
+		let resolution = DisputeResolution {
+			in_favour_of: DisputeParty::Borrower,
+			lender_share: Permill::from_percent(1),
+			action: DisputeAction::Continue,
+		};
+		assert_ok!(Renting::resolve_dispute(
+			RuntimeOrigin::root(),
+			account(&bob()),
+			token.clone(),
+			resolution
+		));
+		assert_eq!(Assets::balance(1, account(&alice())), 0);
+		assert_eq!(Assets::balance(1, account(&bob())), 10 * FEE as u128);
+	});
+}
+
+#[test]
+fn resolve_dispute_extends_the_rental() {
+	new_test_ext().execute_with(|| {
+		let token = mint_token();
+		assert_ok!(rent(&token, due_in(10), ",\"billing\":\"blocks\",\"period\":2"));
+		assert_ok!(Renting::open_dispute(
+			RuntimeOrigin::signed(account(&bob())),
+			Registry::NftCurrency,
+			token.clone(),
+			[2; 32]
+		));
+		run_to_block(12);
+		// the usage right expired at the due date even though the asset stays with the borrower
+		assert_eq!(Renting::user_of(&token), None);
+		let bob_before = balance(&bob());
+
+		assert_noop!(
+			Renting::resolve_dispute(
+				RuntimeOrigin::root(),
+				account(&bob()),
+				token.clone(),
+				ruling(DisputeParty::Borrower, DisputeAction::Extend { due_date: due_in(5) })
+			),
+			Error::<Test>::TimeNotLongEnough
+		);
+		assert_ok!(Renting::resolve_dispute(
+			RuntimeOrigin::root(),
+			account(&bob()),
+			token.clone(),
+			ruling(DisputeParty::Borrower, DisputeAction::Extend { due_date: due_in(20) })
+		));
+		assert_eq!(balance(&bob()), bob_before + DISPUTE_DEPOSIT as u128);
+		assert_eq!(Renting::borrowers(account(&bob()), token.clone()).due_date, due_in(20));
+		assert_eq!(Renting::user_expires(&token), Some(due_in(20)));
+		assert_eq!(Renting::user_of(&token), Some(account(&bob())));
+
+		// instalments resume every two blocks until the new due date
+		let alice_before = balance(&alice());
+		run_to_block(20);
+		assert_eq!(balance(&alice()), alice_before + 4 * net(FEE));
+		assert_eq!(custodian(&token), Some(account(&bob())));
+		run_to_block(21);
+		assert_eq!(custodian(&token), Some(account(&alice())));
+	});
+}
+
+#[test]
+fn resolve_dispute_terminates_the_rental() {
+	new_test_ext().execute_with(|| {
+		let token = mint_token();
+		assert_ok!(rent(&token, due_in(10), ""));
+		assert_ok!(Renting::open_dispute(
+			RuntimeOrigin::signed(account(&alice())),
+			Registry::NftCurrency,
+			token.clone(),
+			[3; 32]
+		));
+		let alice_before = balance(&alice());
+		assert_ok!(Renting::resolve_dispute(
+			RuntimeOrigin::root(),
+			account(&bob()),
+			token.clone(),
+			ruling(DisputeParty::Lender, DisputeAction::Terminate)
+		));
+		assert_eq!(balance(&alice()), alice_before + DISPUTE_DEPOSIT as u128);
+		assert_eq!(custodian(&token), Some(account(&alice())));
+		assert!(Borrowers::<Test>::try_get(account(&bob()), token.clone()).is_err());
+
+		// the return scheduled for the due date is cancelled
+		run_to_block(15);
+		let returns = renting_events()
+			.into_iter()
+			.filter(|event| matches!(event, Event::ReturnAsset(..)))
+			.count();
+		assert_eq!(returns, 1);
+	});
+}
+
+#[test]
+fn disputes_are_restricted_to_the_parties_and_the_arbiter() {
+	new_test_ext().execute_with(|| {
+		let token = mint_token();
+		assert_noop!(
+			Renting::open_dispute(
+				RuntimeOrigin::signed(account(&alice())),
+				Registry::NftCurrency,
+				token.clone(),
+				[0; 32]
+			),
+			Error::<Test>::NoneExist
+		);
+		assert_ok!(rent(&token, due_in(10), ""));
+		assert_noop!(
+			Renting::open_dispute(
+				RuntimeOrigin::signed(account(&charlie())),
+				Registry::NftCurrency,
+				token.clone(),
+				[0; 32]
+			),
+			Error::<Test>::NotParty
+		);
+		let resolution = ruling(DisputeParty::Lender, DisputeAction::Continue);
+		assert_noop!(
+			Renting::resolve_dispute(
+				RuntimeOrigin::root(),
+				account(&bob()),
+				token.clone(),
+				resolution
+			),
+			Error::<Test>::NoDispute
+		);
+
+		assert_ok!(Renting::open_dispute(
+			RuntimeOrigin::signed(account(&bob())),
+			Registry::NftCurrency,
+			token.clone(),
+			[0; 32]
+		));
+		assert_noop!(
+			Renting::open_dispute(
+				RuntimeOrigin::signed(account(&alice())),
+				Registry::NftCurrency,
+				token.clone(),
+				[0; 32]
+			),
+			Error::<Test>::AlreadyDisputed
+		);
+		assert_noop!(
+			Renting::resolve_dispute(
+				RuntimeOrigin::signed(account(&alice())),
+				account(&bob()),
+				token.clone(),
+				resolution
+			),
+			DispatchError::BadOrigin
+		);
+	});
+}
+
+#[test]
+fn reputation_counts_completed_rentals_and_early_stops() {
+	new_test_ext().execute_with(|| {
+		let token = mint_token();
+		assert_ok!(rent(&token, due_in(10), ""));
+		run_to_block(11);
+		let completed = Reputation { completed: 1, ..Default::default() };
+		assert_eq!(Renting::reputation(account(&alice())), completed);
+		assert_eq!(Renting::reputation(account(&bob())), completed);
+
+		assert_ok!(rent(&token, due_in(20), ""));
+		assert_ok!(Renting::stop_renting(RuntimeOrigin::signed(account(&bob())), token.clone()));
+		assert_eq!(
+			Renting::reputation(account(&bob())),
+			Reputation { completed: 1, early_stops: 1, ..Default::default() }
+		);
+		assert_eq!(Renting::reputation(account(&bob())).score(), 0);
+		assert_eq!(Renting::reputation(account(&alice())), completed);
+	});
+}
+
+#[test]
+fn reputation_counts_missed_repayments_and_lost_disputes() {
+	new_test_ext().execute_with(|| {
+		let token = mint_token();
+		assert_ok!(rent(&token, due_in(100), ",\"billing\":\"blocks\",\"period\":2"));
+		Balances::make_free_balance_be(&account(&bob()), 1);
+		// the instalment of block 3 fails, the one of block 5 falls due during the grace period
+		run_to_block(5);
+		assert_eq!(Renting::reputation(account(&bob())).missed_repayments, 2);
+
+		Balances::make_free_balance_be(&account(&bob()), 1_000_000_000);
+		assert_ok!(Renting::open_dispute(
+			RuntimeOrigin::signed(account(&bob())),
+			Registry::NftCurrency,
+			token.clone(),
+			[0; 32]
+		));
+		assert_ok!(Renting::resolve_dispute(
+			RuntimeOrigin::root(),
+			account(&bob()),
+			token.clone(),
+			ruling(DisputeParty::Lender, DisputeAction::Continue)
+		));
+		assert_eq!(Renting::reputation(account(&bob())).disputes_lost, 1);
+		assert_eq!(Renting::reputation(account(&alice())).disputes_lost, 0);
+	});
+}
+
+#[test]
+fn orders_can_require_a_minimum_reputation() {
+	new_test_ext().execute_with(|| {
+		let token = mint_token();
+		let billing = ",\"min_reputation\":2";
+		assert_noop!(rent(&token, due_in(10), billing), Error::<Test>::ReputationTooLow);
+
+		// completed rentals do not make up for a missed instalment
+		let track_record = Reputation { completed: 3, missed_repayments: 1, ..Default::default() };
+		Reputations::<Test>::insert(account(&bob()), track_record);
+		assert_noop!(rent(&token, due_in(10), billing), Error::<Test>::ReputationTooLow);
+
+		let track_record = Reputation { completed: 3, early_stops: 1, ..Default::default() };
+		Reputations::<Test>::insert(account(&bob()), track_record);
+		assert_ok!(rent(&token, due_in(10), billing));
+		assert_eq!(custodian(&token), Some(account(&bob())));
+	});
+}
+
+#[test]
+fn orders_can_require_the_allowlist_of_the_lender() {
+	new_test_ext().execute_with(|| {
+		let token = mint_token();
+		let credential = ",\"credential\":\"allowlist\"";
+		assert_noop!(rent(&token, due_in(10), credential), Error::<Test>::NotQualified);
+		assert_noop!(
+			rent(&token, due_in(10), ",\"credential\":\"passport\""),
+			Error::<Test>::InvalidCredential
+		);
+
+		// the allowlist of another lender does not count
+		assert_ok!(Renting::add_to_allowlist(
+			RuntimeOrigin::signed(account(&charlie())),
+			account(&bob())
+		));
+		assert_noop!(rent(&token, due_in(10), credential), Error::<Test>::NotQualified);
+
+		assert_ok!(Renting::add_to_allowlist(
+			RuntimeOrigin::signed(account(&alice())),
+			account(&bob())
+		));
+		System::assert_has_event(Event::AllowlistAdded(account(&alice()), account(&bob())).into());
+		assert_ok!(rent(&token, due_in(10), credential));
+		assert_eq!(custodian(&token), Some(account(&bob())));
+	});
+}
+
+#[test]
+fn lenders_remove_borrowers_from_their_allowlist() {
+	new_test_ext().execute_with(|| {
+		let token = mint_token();
+		assert_noop!(
+			Renting::remove_from_allowlist(
+				RuntimeOrigin::signed(account(&alice())),
+				account(&bob())
+			),
+			Error::<Test>::NoneExist
+		);
+		assert_ok!(Renting::add_to_allowlist(
+			RuntimeOrigin::signed(account(&alice())),
+			account(&bob())
+		));
+		assert_ok!(Renting::remove_from_allowlist(
+			RuntimeOrigin::signed(account(&alice())),
+			account(&bob())
+		));
+		System::assert_has_event(
+			Event::AllowlistRemoved(account(&alice()), account(&bob())).into(),
+		);
+		assert_eq!(Renting::allowlists(account(&alice()), account(&bob())), None);
+		assert_noop!(
+			rent(&token, due_in(10), ",\"credential\":\"allowlist\""),
+			Error::<Test>::NotQualified
+		);
+	});
+}
+
+/// Item 0 of a `pallet_uniques` collection created by Alice, as a token id.
+fn uniques_item() -> Vec<u8> {
+	assert_ok!(Uniques::create(RuntimeOrigin::signed(account(&alice())), 0, account(&alice())));
+	assert_ok!(Uniques::mint(RuntimeOrigin::signed(account(&alice())), 0, 0, account(&alice())));
+	(0u32, 0u32).encode()
+}
+
+#[test]
+fn uniques_items_are_rented_and_locked() {
+	new_test_ext().execute_with(|| {
+		let item = uniques_item();
+		assert_ok!(rent(&item, due_in(10), ",\"registry\":\"uniques\""));
+		assert_eq!(Renting::item_custodians(&item), Some(account(&bob())));
+		assert_eq!(Uniques::owner(0, 0), Some(account(&alice())));
+		assert_noop!(
+			Uniques::transfer(RuntimeOrigin::signed(account(&alice())), 0, 0, account(&charlie())),
+			pallet_uniques::Error::<Test>::Locked
+		);
+
+		run_to_block(11);
+		assert_eq!(Renting::item_custodians(&item), None);
+		assert_ok!(Uniques::transfer(
+			RuntimeOrigin::signed(account(&alice())),
+			0,
+			0,
+			account(&charlie())
+		));
+	});
+}
+
+#[test]
+fn uniques_rentals_stop_and_dispute_like_any_other() {
+	new_test_ext().execute_with(|| {
+		let item = uniques_item();
+		assert_ok!(rent(&item, due_in(10), ",\"registry\":\"uniques\""));
+		assert_noop!(
+			Renting::open_dispute(
+				RuntimeOrigin::signed(account(&alice())),
+				Registry::NftCurrency,
+				item.clone(),
+				[0; 32]
+			),
+			Error::<Test>::NoneExist
+		);
+		assert_ok!(Renting::open_dispute(
+			RuntimeOrigin::signed(account(&alice())),
+			Registry::Uniques,
+			item.clone(),
+			[0; 32]
+		));
+		assert_ok!(Renting::resolve_dispute(
+			RuntimeOrigin::root(),
+			account(&bob()),
+			item.clone(),
+			ruling(DisputeParty::Lender, DisputeAction::Continue)
+		));
+		assert_ok!(Renting::stop_renting(RuntimeOrigin::signed(account(&bob())), item.clone()));
+		assert_eq!(Renting::item_custodians(&item), None);
+	});
+}
+
+#[test]
+fn burning_a_rented_item_ends_the_rental() {
+	new_test_ext().execute_with(|| {
+		let item = uniques_item();
+		let terms = ",\"registry\":\"uniques\",\"billing\":\"blocks\",\"period\":2";
+		assert_ok!(rent(&item, due_in(10), terms));
+		// the locker only stops transfers, the owner can still burn the item
+		assert_ok!(Uniques::burn(RuntimeOrigin::signed(account(&alice())), 0, 0, None));
+
+		// the instalment of block 3 finds nothing left to pay for
+		let bob_before = balance(&bob());
+		run_to_block(3);
+		assert_eq!(balance(&bob()), bob_before);
+		assert!(Borrowers::<Test>::try_get(account(&bob()), item.clone()).is_err());
+		assert_eq!(Renting::item_custodians(&item), None);
+		assert_eq!(Renting::user_of(&item), None);
+		System::assert_has_event(
+			Event::RentalTokenBurned(account(&bob()), account(&alice()), item.clone()).into(),
+		);
+
+		// an item minted again with the same id is not locked
+		assert_ok!(Uniques::mint(
+			RuntimeOrigin::signed(account(&alice())),
+			0,
+			0,
+			account(&alice())
+		));
+		assert_ok!(Uniques::transfer(
+			RuntimeOrigin::signed(account(&alice())),
+			0,
+			0,
+			account(&charlie())
+		));
+	});
+}
+
+#[test]
+fn anyone_ends_the_rental_of_a_burned_item() {
+	new_test_ext().execute_with(|| {
+		let item = uniques_item();
+		assert_ok!(rent(&item, due_in(10), ",\"registry\":\"uniques\""));
+		assert_ok!(Uniques::burn(RuntimeOrigin::signed(account(&alice())), 0, 0, None));
+
+		assert_ok!(Renting::return_asset(
+			RuntimeOrigin::signed(account(&charlie())),
+			account(&bob()),
+			item.clone()
+		));
+		assert!(Borrowers::<Test>::try_get(account(&bob()), item.clone()).is_err());
+		assert_eq!(Renting::item_custodians(&item), None);
+		// the rental ended without being completed
+		assert_eq!(Renting::reputation(account(&bob())), Reputation::default());
+		assert_eq!(Renting::reputation(account(&alice())), Reputation::default());
+	});
+}
+
+#[test]
+fn order_registry_must_hold_the_token() {
+	new_test_ext().execute_with(|| {
+		let token = mint_token();
+		let item = uniques_item();
+		// the token of pallet_nft_currency is not an item of pallet_uniques, and the other way round
+		assert_noop!(
+			rent(&token, due_in(10), ",\"registry\":\"uniques\""),
+			Error::<Test>::CannotTransferCustodian
+		);
+		assert_noop!(rent(&item, due_in(10), ""), Error::<Test>::CannotTransferCustodian);
+		assert_noop!(
+			rent(&item, due_in(10), ",\"registry\":\"rmrk\""),
+			Error::<Test>::InvalidRegistry
+		);
+	});
+}
+
+#[test]
+fn borrower_holds_the_usage_right_until_the_due_date() {
+	new_test_ext().execute_with(|| {
+		let token = mint_token();
+		assert_ok!(rent(&token, due_in(10), ""));
+		assert_eq!(
+			Renting::usage_rights(&token),
+			Some(UsageRight { user: account(&bob()), expires: due_in(10) })
+		);
+		assert_eq!(Renting::user_of(&token), Some(account(&bob())));
+		assert_eq!(
+			<UsageRightTokens<Test> as Inspect<_>>::owner(&(), &token),
+			Some(account(&bob()))
+		);
+		assert_eq!(
+			<UsageRightTokens<Test> as Inspect<_>>::attribute(&(), &token, b"expires"),
+			Some(due_in(10).encode())
+		);
+		assert!(!<UsageRightTokens<Test> as Inspect<_>>::can_transfer(&(), &token));
+		System::assert_has_event(
+			Event::UpdateUser(token.clone(), Some(account(&bob())), due_in(10)).into(),
+		);
+
+		// burned with the return of the asset
+		run_to_block(11);
+		assert_eq!(Renting::usage_rights(&token), None);
+		assert_eq!(Renting::user_of(&token), None);
+		System::assert_has_event(Event::UpdateUser(token, None, 0).into());
+	});
+}
+
+#[test]
+fn usage_right_is_burned_when_renting_stops() {
+	new_test_ext().execute_with(|| {
+		let token = mint_token();
+		assert_ok!(rent(&token, due_in(10), ""));
+		assert_ok!(Renting::stop_renting(RuntimeOrigin::signed(account(&bob())), token.clone()));
+		assert_eq!(Renting::usage_rights(&token), None);
+		assert_eq!(<UsageRightTokens<Test> as Inspect<_>>::owner(&(), &token), None);
+	});
+}
+
+#[test]
+fn usage_right_goes_back_to_the_borrower_after_a_sublet() {
+	new_test_ext().execute_with(|| {
+		let token = mint_token();
+		assert_ok!(rent(&token, due_in(10), ""));
+		let message_left = order(&bob(), None, &token, FEE, due_in(5), "");
+		let message_right = order(&bob(), Some(&charlie()), &token, FEE, due_in(5), "");
+		assert_ok!(Renting::create_rental(
+			RuntimeOrigin::signed(account(&charlie())),
+			account(&bob()),
+			account(&charlie()),
+			message_left.clone(),
+			sign(&bob(), &message_left),
+			message_right.clone(),
+			sign(&charlie(), &message_right),
+		));
+		assert_eq!(Renting::user_of(&token), Some(account(&charlie())));
+
+		run_to_block(6);
+		assert_eq!(
+			Renting::usage_rights(&token),
+			Some(UsageRight { user: account(&bob()), expires: due_in(10) })
+		);
+		run_to_block(11);
+		assert_eq!(Renting::usage_rights(&token), None);
+	});
+}
+
+/// A cancelled order and a rental of the returned token whose return is no longer scheduled,
+/// both due at block 6.
+fn stale_entries() -> (Vec<u8>, Vec<StaleEntry<AccountId>>) {
+	let token = mint_token();
+	let cancelled = order(&alice(), None, &token, FEE, due_in(5), "");
+	assert_ok!(Renting::cancel_offer(RuntimeOrigin::signed(account(&alice())), cancelled, true));
+	let key = crate::CancelOrder::<Test>::iter_keys().next().unwrap();
+	assert_ok!(rent(&token, due_in(5), ""));
+	assert_ok!(Scheduler::cancel_named(Renting::task_id(
+		RentalTask::Return,
+		&account(&bob()),
+		&token
+	)));
+	let entries = vec![
+		StaleEntry::CancelledOrder(key),
+		StaleEntry::ExpiredRental(account(&bob()), token.clone()),
+	];
+	(token, entries)
+}
+
+#[test]
+fn offchain_worker_submits_stale_entries() {
+	let (pool, state) = testing::TestTransactionPoolExt::new();
+	let (offchain, _) = testing::TestOffchainExt::new();
+	let mut ext = new_test_ext();
+	ext.register_extension(OffchainWorkerExt::new(offchain));
+	ext.register_extension(TransactionPoolExt::new(pool));
+	ext.execute_with(|| {
+		let (_, entries) = stale_entries();
+		// nothing is stale before the due date
+		Renting::offchain_worker(10);
+		assert!(state.read().transactions.is_empty());
+
+		run_to_block(10);
+		// the worker only scans every `CleanupInterval` blocks
+		Renting::offchain_worker(11);
+		assert!(state.read().transactions.is_empty());
+		Renting::offchain_worker(10);
+		let tx = state.write().transactions.pop().unwrap();
+		let tx = Extrinsic::decode(&mut &tx[..]).unwrap();
+		assert_eq!(tx.signature, None);
+		assert_eq!(tx.call, RuntimeCall::Renting(crate::Call::cleanup { entries }));
+	});
+}
+
+#[test]
+fn cleanup_removes_stale_entries_once() {
+	new_test_ext().execute_with(|| {
+		let (token, entries) = stale_entries();
+		let call = crate::Call::cleanup { entries: entries.clone() };
+		assert_eq!(
+			Renting::validate_unsigned(TransactionSource::External, &call),
+			InvalidTransaction::Stale.into()
+		);
+
+		run_to_block(10);
+		assert!(Renting::validate_unsigned(TransactionSource::External, &call).is_ok());
+		assert_ok!(Renting::cleanup(RuntimeOrigin::none(), entries.clone()));
+		assert_eq!(crate::CancelOrder::<Test>::iter().count(), 0);
+		assert!(Renting::rental(&account(&bob()), &token).is_none());
+		assert_eq!(custodian(&token), Some(account(&alice())));
+		System::assert_last_event(Event::CleanedUp(2).into());
+
+		assert_eq!(
+			Renting::validate_unsigned(TransactionSource::External, &call),
+			InvalidTransaction::Stale.into()
+		);
+		assert_ok!(Renting::cleanup(RuntimeOrigin::none(), entries));
+		System::assert_last_event(Event::CleanedUp(0).into());
+	});
+}
+
+#[test]
+fn cleanup_is_unsigned_and_bounded() {
+	new_test_ext().execute_with(|| {
+		let (_, entries) = stale_entries();
+		run_to_block(10);
+		assert_noop!(
+			Renting::cleanup(RuntimeOrigin::signed(account(&alice())), entries.clone()),
+			sp_runtime::traits::BadOrigin
+		);
+		let empty = crate::Call::cleanup { entries: vec![] };
+		assert_eq!(
+			Renting::validate_unsigned(TransactionSource::External, &empty),
+			InvalidTransaction::ExhaustsResources.into()
+		);
+		let too_many = crate::Call::cleanup { entries: vec![entries[0].clone(); 17] };
+		assert_eq!(
+			Renting::validate_unsigned(TransactionSource::External, &too_many),
+			InvalidTransaction::ExhaustsResources.into()
+		);
+	});
+}
+
+#[test]
+fn admin_origin_sets_marketplace_fee() {
+	new_test_ext().execute_with(|| {
+		let fee = Permill::from_percent(5);
+		assert_noop!(
+			Renting::set_marketplace_fee(RuntimeOrigin::signed(account(&alice())), fee),
+			sp_runtime::traits::BadOrigin
+		);
+		assert_ok!(Renting::set_marketplace_fee(RuntimeOrigin::root(), fee));
+		assert_eq!(Renting::marketplace_fee(), fee);
+		System::assert_last_event(Event::MarketplaceFeeSet(fee).into());
+	});
+}
+
+#[test]
+fn migration_hands_the_rentals_of_the_old_hooks_to_the_scheduler() {
+	new_test_ext().execute_with(|| {
+		StorageVersion::new(0).put::<Renting>();
+		let token = mint_token();
+		assert_ok!(<NftCurrency as NonFungibleToken<AccountId>>::transfer_custodian(
+			account(&alice()),
+			account(&bob()),
+			token.clone()
+		));
+		// a daily rental due at block 11 with its next instalment at block 5
+		let rental = v1::OldOrder {
+			lender: account_to_bytes(&account(&alice())).unwrap(),
+			borrower: account_to_bytes(&account(&bob())).unwrap(),
+			fee: FEE,
+			token: token.clone(),
+			due_date: due_in(10),
+			paid_type: 1,
+		};
+		let key = Borrowers::<Test>::hashed_key_for(account(&bob()), token.clone());
+		frame_support::storage::unhashed::put(&key, &rental);
+		v1::DueBlock::<Test>::insert(11, vec![rental.clone()]);
+		v1::Repayment::<Test>::insert(5, vec![rental.clone()]);
+		let cancelled = v1::OldOrder { borrower: [0u8; 32], paid_type: 0, ..rental };
+		v1::CancelOrder::<Test>::insert(cancelled.encode(), cancelled.clone());
+
+		v1::MigrateToV1::<Test>::on_runtime_upgrade();
+		assert_eq!(Renting::on_chain_storage_version(), 1);
+		assert_eq!(v1::DueBlock::<Test>::iter().count(), 0);
+		assert_eq!(v1::Repayment::<Test>::iter().count(), 0);
+		let order = Renting::rental(&account(&bob()), &token).unwrap();
+		assert_eq!(
+			order.billing,
+			BillingPlan::EverySeconds { period: 86_400, first: FirstPayment::Upfront }
+		);
+		// the message of the cancelled order still parses to a cancelled order
+		let parsed = Order {
+			lender: cancelled.lender,
+			fee: FEE,
+			token: token.clone(),
+			due_date: due_in(10),
+			..Order::new()
+		};
+		assert!(CancelOrder::<Test>::contains_key(parsed.encode()));
+
+		let alice_before = balance(&alice());
+		run_to_block(5);
+		assert_eq!(balance(&alice()), alice_before + net(FEE));
+		run_to_block(11);
+		assert_eq!(custodian(&token), Some(account(&alice())));
+		assert!(Borrowers::<Test>::try_get(account(&bob()), token).is_err());
+	});
+}
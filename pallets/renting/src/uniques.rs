@@ -0,0 +1,114 @@
+//! Items of `pallet_uniques` as rentable tokens.
+//!
+//! A token id is the SCALE encoded `(collection, item)` pair. The owner in `pallet_uniques` stays
+//! the lender during a rental, the borrower is recorded in `ItemCustodians` and the pallet, set as
+//! the `Locker` of `pallet_uniques`, keeps the item from being transferred until it is returned.
+//!
+//! `pallet_uniques` does not ask the locker before a burn, so the owner or the collection admin
+//! can still burn a rented item. The rental then ends at its next return or instalment, or as
+//! soon as anyone calls `return_asset`, without payments or reputation for either party.
+
+use crate::{Config, Error, ItemCustodians, Pallet};
+use codec::{DecodeAll, Encode};
+use frame_support::{
+	dispatch::{DispatchError, DispatchResult},
+	ensure,
+	traits::{
+		tokens::nonfungibles::{Inspect, Transfer},
+		Locker,
+	},
+};
+use pallet_nft_currency::NonFungibleToken;
+use sp_std::{marker::PhantomData, vec::Vec};
+
+/// `NonFungibleToken` view of the items of a `pallet_uniques` instance.
+pub struct UniquesItems<T, I = ()>(PhantomData<(T, I)>);
+
+impl<T, I> UniquesItems<T, I>
+where
+	T: Config + pallet_uniques::Config<I>,
+	I: 'static,
+{
+	fn item(token_id: &[u8]) -> Option<(T::CollectionId, T::ItemId)> {
+		DecodeAll::decode_all(&mut &token_id[..]).ok()
+	}
+
+	fn owner(token_id: &[u8]) -> Option<T::AccountId> {
+		let (collection, item) = Self::item(token_id)?;
+		pallet_uniques::Pallet::<T, I>::owner(&collection, &item)
+	}
+}
+
+impl<T, I> NonFungibleToken<T::AccountId> for UniquesItems<T, I>
+where
+	T: Config + pallet_uniques::Config<I>,
+	I: 'static,
+{
+	fn token_uri(token_id: Vec<u8>) -> Option<Vec<u8>> {
+		let (collection, item) = Self::item(&token_id)?;
+		// the metadata of the item is its attribute without key
+		pallet_uniques::Pallet::<T, I>::attribute(&collection, &item, &[])
+	}
+
+	fn custodian_of_token(token_id: Vec<u8>) -> Option<T::AccountId> {
+		let owner = Self::owner(&token_id)?;
+		Some(ItemCustodians::<T>::get(&token_id).unwrap_or(owner))
+	}
+
+	fn owner_of_token(token_id: Vec<u8>) -> Option<T::AccountId> {
+		Self::owner(&token_id)
+	}
+
+	fn mint(_owner: T::AccountId) -> Result<Vec<u8>, DispatchError> {
+		Err(DispatchError::Other("items are minted in their collection with pallet_uniques"))
+	}
+
+	fn transfer_ownership(
+		from: T::AccountId,
+		to: T::AccountId,
+		token_id: Vec<u8>,
+	) -> DispatchResult {
+		let (collection, item) = Self::item(&token_id).ok_or(Error::<T>::InvalidToken)?;
+		ensure!(Self::owner(&token_id) == Some(from), Error::<T>::NotOwner);
+		// fails with `Locked` while the item is rented out
+		<pallet_uniques::Pallet<T, I> as Transfer<_>>::transfer(&collection, &item, &to)
+	}
+
+	fn transfer_custodian(
+		from: T::AccountId,
+		to: T::AccountId,
+		token_id: Vec<u8>,
+	) -> DispatchResult {
+		let owner = Self::owner(&token_id).ok_or(Error::<T>::NoneExist)?;
+		ensure!(Self::custodian_of_token(token_id.clone()) == Some(from), Error::<T>::NotOwner);
+		if to == owner {
+			ItemCustodians::<T>::remove(&token_id);
+		} else {
+			ItemCustodians::<T>::insert(&token_id, to);
+		}
+		Ok(())
+	}
+
+	fn set_token_uri(_token_id: Vec<u8>, _token_uri: Vec<u8>) -> DispatchResult {
+		Err(DispatchError::Other("item metadata is set with pallet_uniques"))
+	}
+
+	fn is_approve_for_all(_account_approve: (T::AccountId, T::AccountId)) -> bool {
+		false
+	}
+
+	fn approve(_from: T::AccountId, _to: T::AccountId, _token_id: Vec<u8>) -> DispatchResult {
+		Err(DispatchError::Other("items are approved with pallet_uniques"))
+	}
+
+	fn set_approve_for_all(_from: T::AccountId, _to: T::AccountId) -> DispatchResult {
+		Err(DispatchError::Other("items are approved with pallet_uniques"))
+	}
+}
+
+/// Items are locked in `pallet_uniques` while they are rented out, which only stops transfers.
+impl<T: Config, CollectionId: Encode, ItemId: Encode> Locker<CollectionId, ItemId> for Pallet<T> {
+	fn is_locked(collection: CollectionId, item: ItemId) -> bool {
+		ItemCustodians::<T>::contains_key((collection, item).encode())
+	}
+}
@@ -0,0 +1,44 @@
+//! Usage rights of rented tokens, after ERC-4907.
+//!
+//! A rental grants its borrower the right to use the token, its `user`, until the due date of the
+//! rental, its `expires`. The right is minted when the rental starts and burned when the token
+//! goes back to the lender. `UsageRightTokens` lets other pallets inspect the rights as tokens of
+//! a single collection, owned by their user until they expire.
+
+use crate::{Config, Pallet, UsageRights};
+use frame_support::{pallet_prelude::*, traits::tokens::nonfungibles::Inspect};
+use sp_std::{marker::PhantomData, vec::Vec};
+
+/// Right of `user` to use a rented token until `expires`.
+#[derive(Clone, Encode, Decode, PartialEq, Eq, TypeInfo, Debug)]
+pub struct UsageRight<AccountId> {
+	/// Borrower of the rental.
+	pub user: AccountId,
+	/// Unix time in seconds the right expires at, the due date of the rental.
+	pub expires: u64,
+}
+
+/// Usage rights seen through `nonfungibles::Inspect`, the item id being the rented token id.
+pub struct UsageRightTokens<T>(PhantomData<T>);
+
+impl<T: Config> Inspect<T::AccountId> for UsageRightTokens<T> {
+	type ItemId = Vec<u8>;
+	type CollectionId = ();
+
+	fn owner(_collection: &(), item: &Vec<u8>) -> Option<T::AccountId> {
+		Pallet::<T>::user_of(item)
+	}
+
+	fn attribute(_collection: &(), item: &Vec<u8>, key: &[u8]) -> Option<Vec<u8>> {
+		if key == b"expires" {
+			UsageRights::<T>::get(item).map(|right| right.expires.encode())
+		} else {
+			None
+		}
+	}
+
+	/// Rights are bound to the rental, they only move with it.
+	fn can_transfer(_collection: &(), _item: &Vec<u8>) -> bool {
+		false
+	}
+}
@@ -0,0 +1,173 @@
+//! Provisional weights for pallet_renting, they are NOT benchmark output.
+//!
+//! The values were estimated by hand from the storage each call touches, transaction fees will not
+//! match the real cost of the calls until this file is replaced by the output of
+//! `./scripts/benchmark.sh pallet_renting` run on reference hardware. The script runs the Substrate
+//! benchmark CLI with `--steps 50 --repeat 20` and `.maintain/frame-weight-template.hbs`.
+
+#![cfg_attr(rustfmt, rustfmt_skip)]
+#![allow(unused_parens)]
+#![allow(unused_imports)]
+
+use frame_support::{traits::Get, weights::{Weight, constants::RocksDbWeight}};
+use sp_std::marker::PhantomData;
+
+/// Weight functions needed for pallet_renting.
+pub trait WeightInfo {
+	fn create_rental() -> Weight;
+	fn cancel_offer() -> Weight;
+	fn stop_renting() -> Weight;
+	fn extend_rental() -> Weight;
+	fn return_asset() -> Weight;
+	fn collect_repayment() -> Weight;
+	fn retry_payment() -> Weight;
+	fn set_marketplace_fee() -> Weight;
+	fn open_dispute() -> Weight;
+	fn resolve_dispute() -> Weight;
+	fn add_to_allowlist() -> Weight;
+	fn remove_from_allowlist() -> Weight;
+	fn cleanup(n: u32, ) -> Weight;
+}
+
+/// Provisional weights for pallet_renting, see the module documentation.
+pub struct SubstrateWeight<T>(PhantomData<T>);
+impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
+	fn create_rental() -> Weight {
+		Weight::from_ref_time(97_514_000 as u64)
+			.saturating_add(T::DbWeight::get().reads(15 as u64))
+			.saturating_add(T::DbWeight::get().writes(8 as u64))
+	}
+	fn cancel_offer() -> Weight {
+		Weight::from_ref_time(31_206_000 as u64)
+			.saturating_add(T::DbWeight::get().reads(1 as u64))
+			.saturating_add(T::DbWeight::get().writes(1 as u64))
+	}
+	fn stop_renting() -> Weight {
+		Weight::from_ref_time(62_380_000 as u64)
+			.saturating_add(T::DbWeight::get().reads(10 as u64))
+			.saturating_add(T::DbWeight::get().writes(7 as u64))
+	}
+	fn extend_rental() -> Weight {
+		Weight::from_ref_time(104_127_000 as u64)
+			.saturating_add(T::DbWeight::get().reads(11 as u64))
+			.saturating_add(T::DbWeight::get().writes(8 as u64))
+	}
+	fn return_asset() -> Weight {
+		Weight::from_ref_time(54_902_000 as u64)
+			.saturating_add(T::DbWeight::get().reads(11 as u64))
+			.saturating_add(T::DbWeight::get().writes(6 as u64))
+	}
+	fn collect_repayment() -> Weight {
+		Weight::from_ref_time(78_631_000 as u64)
+			.saturating_add(T::DbWeight::get().reads(10 as u64))
+			.saturating_add(T::DbWeight::get().writes(5 as u64))
+	}
+	fn retry_payment() -> Weight {
+		Weight::from_ref_time(61_447_000 as u64)
+			.saturating_add(T::DbWeight::get().reads(6 as u64))
+			.saturating_add(T::DbWeight::get().writes(4 as u64))
+	}
+	fn set_marketplace_fee() -> Weight {
+		Weight::from_ref_time(14_210_000 as u64)
+			.saturating_add(T::DbWeight::get().writes(1 as u64))
+	}
+	fn open_dispute() -> Weight {
+		Weight::from_ref_time(58_633_000 as u64)
+			.saturating_add(T::DbWeight::get().reads(6 as u64))
+			.saturating_add(T::DbWeight::get().writes(3 as u64))
+	}
+	fn resolve_dispute() -> Weight {
+		Weight::from_ref_time(96_845_000 as u64)
+			.saturating_add(T::DbWeight::get().reads(14 as u64))
+			.saturating_add(T::DbWeight::get().writes(12 as u64))
+	}
+	fn add_to_allowlist() -> Weight {
+		Weight::from_ref_time(18_532_000 as u64)
+			.saturating_add(T::DbWeight::get().writes(1 as u64))
+	}
+	fn remove_from_allowlist() -> Weight {
+		Weight::from_ref_time(21_947_000 as u64)
+			.saturating_add(T::DbWeight::get().reads(1 as u64))
+			.saturating_add(T::DbWeight::get().writes(1 as u64))
+	}
+	/// The range of component `n` is `[1, 16]`.
+	fn cleanup(n: u32, ) -> Weight {
+		Weight::from_ref_time(9_871_000 as u64)
+			// Standard Error: 21_402
+			.saturating_add(Weight::from_ref_time(47_316_000 as u64).saturating_mul(n as u64))
+			.saturating_add(T::DbWeight::get().reads(1 as u64))
+			.saturating_add(T::DbWeight::get().reads((11 as u64).saturating_mul(n as u64)))
+			.saturating_add(T::DbWeight::get().writes((6 as u64).saturating_mul(n as u64)))
+	}
+}
+
+// For backwards compatibility and tests
+impl WeightInfo for () {
+	fn create_rental() -> Weight {
+		Weight::from_ref_time(97_514_000 as u64)
+			.saturating_add(RocksDbWeight::get().reads(15 as u64))
+			.saturating_add(RocksDbWeight::get().writes(8 as u64))
+	}
+	fn cancel_offer() -> Weight {
+		Weight::from_ref_time(31_206_000 as u64)
+			.saturating_add(RocksDbWeight::get().reads(1 as u64))
+			.saturating_add(RocksDbWeight::get().writes(1 as u64))
+	}
+	fn stop_renting() -> Weight {
+		Weight::from_ref_time(62_380_000 as u64)
+			.saturating_add(RocksDbWeight::get().reads(10 as u64))
+			.saturating_add(RocksDbWeight::get().writes(7 as u64))
+	}
+	fn extend_rental() -> Weight {
+		Weight::from_ref_time(104_127_000 as u64)
+			.saturating_add(RocksDbWeight::get().reads(11 as u64))
+			.saturating_add(RocksDbWeight::get().writes(8 as u64))
+	}
+	fn return_asset() -> Weight {
+		Weight::from_ref_time(54_902_000 as u64)
+			.saturating_add(RocksDbWeight::get().reads(11 as u64))
+			.saturating_add(RocksDbWeight::get().writes(6 as u64))
+	}
+	fn collect_repayment() -> Weight {
+		Weight::from_ref_time(78_631_000 as u64)
+			.saturating_add(RocksDbWeight::get().reads(10 as u64))
+			.saturating_add(RocksDbWeight::get().writes(5 as u64))
+	}
+	fn retry_payment() -> Weight {
+		Weight::from_ref_time(61_447_000 as u64)
+			.saturating_add(RocksDbWeight::get().reads(6 as u64))
+			.saturating_add(RocksDbWeight::get().writes(4 as u64))
+	}
+	fn set_marketplace_fee() -> Weight {
+		Weight::from_ref_time(14_210_000 as u64)
+			.saturating_add(RocksDbWeight::get().writes(1 as u64))
+	}
+	fn open_dispute() -> Weight {
+		Weight::from_ref_time(58_633_000 as u64)
+			.saturating_add(RocksDbWeight::get().reads(6 as u64))
+			.saturating_add(RocksDbWeight::get().writes(3 as u64))
+	}
+	fn resolve_dispute() -> Weight {
+		Weight::from_ref_time(96_845_000 as u64)
+			.saturating_add(RocksDbWeight::get().reads(14 as u64))
+			.saturating_add(RocksDbWeight::get().writes(12 as u64))
+	}
+	fn add_to_allowlist() -> Weight {
+		Weight::from_ref_time(18_532_000 as u64)
+			.saturating_add(RocksDbWeight::get().writes(1 as u64))
+	}
+	fn remove_from_allowlist() -> Weight {
+		Weight::from_ref_time(21_947_000 as u64)
+			.saturating_add(RocksDbWeight::get().reads(1 as u64))
+			.saturating_add(RocksDbWeight::get().writes(1 as u64))
+	}
+	/// The range of component `n` is `[1, 16]`.
+	fn cleanup(n: u32, ) -> Weight {
+		Weight::from_ref_time(9_871_000 as u64)
+			// Standard Error: 21_402
+			.saturating_add(Weight::from_ref_time(47_316_000 as u64).saturating_mul(n as u64))
+			.saturating_add(RocksDbWeight::get().reads(1 as u64))
+			.saturating_add(RocksDbWeight::get().reads((11 as u64).saturating_mul(n as u64)))
+			.saturating_add(RocksDbWeight::get().writes((6 as u64).saturating_mul(n as u64)))
+	}
+}
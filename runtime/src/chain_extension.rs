@@ -0,0 +1,233 @@
+//! Chain extension giving ink! contracts access to `pallet_nft_currency` and `pallet_renting`.
+//!
+//! Function ids carry the extension id in their upper 16 bits. Calls with `NFT_RENTING_ID` are
+//! handled here, every other id goes to the assets extension so existing contracts keep working.
+//!
+//! Calls are dispatched with the contract as signed origin and charged the weight of the
+//! extrinsic, queries are charged their storage reads and the bytes they write. A failed dispatch
+//! returns `FAILED` as status code instead of trapping the contract.
+
+use codec::{Decode, Encode};
+use frame_support::{
+	dispatch::{DispatchError, GetDispatchInfo},
+	traits::Get,
+};
+use frame_system::RawOrigin;
+use pallet_assets_chain_extension::substrate::AssetsExtension;
+use pallet_contracts::chain_extension::{
+	BufInBufOutState, ChainExtension, Environment, Ext, InitState, RetVal, SysConfig, UncheckedFrom,
+};
+use pallet_nft_currency::NonFungibleToken;
+use sp_runtime::traits::Dispatchable;
+use sp_std::vec::Vec;
+
+use crate::{AccountId, NftCurrency, Renting, Runtime, RuntimeCall};
+
+/// Extension id of the NFT and renting calls.
+pub const NFT_RENTING_ID: u16 = 0x4e52;
+
+/// Status code of a call that succeeded.
+pub const SUCCESS: u32 = 0;
+/// Status code of a call whose dispatch failed.
+pub const FAILED: u32 = 1;
+
+/// Functions of the NFT and renting extension.
+#[derive(Debug, PartialEq, Eq)]
+enum Func {
+	/// `(token_id: Vec<u8>) -> Option<AccountId>`
+	OwnerOf,
+	/// `(token_id: Vec<u8>) -> Option<AccountId>`
+	CustodianOf,
+	/// `(to: AccountId, token_id: Vec<u8>)`, transfer a token owned by the contract.
+	Transfer,
+	/// `(to: AccountId, token_id: Vec<u8>)`, approve `to` on a token held by the contract.
+	Approve,
+	/// `(lender, borrower, message_left, signature_left, message_right, signature_right)`
+	CreateRental,
+	/// `(token_id: Vec<u8>)`, end a rental of the contract early.
+	StopRenting,
+	/// `(borrower: AccountId, token_id: Vec<u8>) -> Option<RentalInfo>`
+	Rental,
+	/// `(account: AccountId) -> Reputation`
+	Reputation,
+	/// `(token_id: Vec<u8>) -> Option<AccountId>`, account allowed to use the token now.
+	UserOf,
+}
+
+impl TryFrom<u16> for Func {
+	type Error = DispatchError;
+
+	fn try_from(func_id: u16) -> Result<Self, Self::Error> {
+		match func_id {
+			0x0001 => Ok(Func::OwnerOf),
+			0x0002 => Ok(Func::CustodianOf),
+			0x0003 => Ok(Func::Transfer),
+			0x0004 => Ok(Func::Approve),
+			0x0101 => Ok(Func::CreateRental),
+			0x0102 => Ok(Func::StopRenting),
+			0x0103 => Ok(Func::Rental),
+			0x0104 => Ok(Func::Reputation),
+			0x0105 => Ok(Func::UserOf),
+			_ => Err(DispatchError::Other("unknown function of the NFT and renting extension")),
+		}
+	}
+}
+
+/// What a contract gets to know about a rental, kept stable whatever `Order` becomes.
+#[derive(Encode)]
+struct RentalInfo {
+	lender: AccountId,
+	fee: u64,
+	due_date: u64,
+	asset_id: Option<u32>,
+}
+
+#[derive(Decode)]
+struct CreateRentalInput {
+	lender: AccountId,
+	borrower: AccountId,
+	message_left: Vec<u8>,
+	signature_left: Vec<u8>,
+	message_right: Vec<u8>,
+	signature_right: Vec<u8>,
+}
+
+/// Assets extension composed with the NFT and renting calls.
+#[derive(Default)]
+pub struct NftRentingExtension;
+
+impl ChainExtension<Runtime> for NftRentingExtension {
+	fn call<E>(&mut self, env: Environment<E, InitState>) -> Result<RetVal, DispatchError>
+	where
+		E: Ext<T = Runtime>,
+		<E::T as SysConfig>::AccountId: UncheckedFrom<<E::T as SysConfig>::Hash> + AsRef<[u8]>,
+	{
+		if env.ext_id() != NFT_RENTING_ID {
+			return AssetsExtension::default().call(env);
+		}
+		let func = Func::try_from(env.func_id())?;
+		let mut env = env.buf_in_buf_out();
+		let db = <Runtime as frame_system::Config>::DbWeight::get();
+		match func {
+			Func::OwnerOf | Func::CustodianOf => {
+				let token_id: Vec<u8> = env.read_as_unbounded(env.in_len())?;
+				env.charge_weight(db.reads(1))?;
+				let account = if func == Func::OwnerOf {
+					NftCurrency::owner_of_token(token_id)
+				} else {
+					NftCurrency::custodian_of_token(token_id)
+				};
+				write_output(&mut env, &account)?;
+			},
+			Func::Transfer => {
+				let (to, token_id): (AccountId, Vec<u8>) = env.read_as_unbounded(env.in_len())?;
+				let call = pallet_nft_currency::Call::transfer_ownership { to, token_id };
+				return dispatch(&mut env, call.into());
+			},
+			Func::Approve => {
+				let (to, token_id): (AccountId, Vec<u8>) = env.read_as_unbounded(env.in_len())?;
+				return dispatch(
+					&mut env,
+					pallet_nft_currency::Call::approve { to, token_id }.into(),
+				);
+			},
+			Func::CreateRental => {
+				let input: CreateRentalInput = env.read_as_unbounded(env.in_len())?;
+				let call = pallet_renting::Call::create_rental {
+					lender: input.lender,
+					borrower: input.borrower,
+					message_left: input.message_left,
+					signature_left: input.signature_left,
+					message_right: input.message_right,
+					signature_right: input.signature_right,
+				};
+				return dispatch(&mut env, call.into());
+			},
+			Func::StopRenting => {
+				let token_id: Vec<u8> = env.read_as_unbounded(env.in_len())?;
+				return dispatch(&mut env, pallet_renting::Call::stop_renting { token_id }.into());
+			},
+			Func::Rental => {
+				let (borrower, token_id): (AccountId, Vec<u8>) =
+					env.read_as_unbounded(env.in_len())?;
+				env.charge_weight(db.reads(1))?;
+				let rental = Renting::rental(&borrower, &token_id).map(|order| RentalInfo {
+					lender: order.lender().into(),
+					fee: order.fee(),
+					due_date: order.due_date(),
+					asset_id: order.asset_id(),
+				});
+				write_output(&mut env, &rental)?;
+			},
+			Func::Reputation => {
+				let account: AccountId = env.read_as_unbounded(env.in_len())?;
+				env.charge_weight(db.reads(1))?;
+				write_output(&mut env, &Renting::reputation(account))?;
+			},
+			Func::UserOf => {
+				let token_id: Vec<u8> = env.read_as_unbounded(env.in_len())?;
+				// the usage right and the current time
+				env.charge_weight(db.reads(2))?;
+				write_output(&mut env, &Renting::user_of(&token_id))?;
+			},
+		}
+		Ok(RetVal::Converging(SUCCESS))
+	}
+}
+
+/// Write the `output` of a query to the contract, charged per byte like the return data of a
+/// contract call.
+fn write_output<E>(
+	env: &mut Environment<E, BufInBufOutState>,
+	output: &impl Encode,
+) -> Result<(), DispatchError>
+where
+	E: Ext<T = Runtime>,
+{
+	let per_byte = <Runtime as pallet_contracts::Config>::Schedule::get()
+		.host_fn_weights
+		.return_per_byte;
+	env.write(&output.encode(), false, Some(per_byte))
+}
+
+/// Dispatch `call` signed by the contract, charging its weight and refunding what it did not use.
+fn dispatch<E>(
+	env: &mut Environment<E, BufInBufOutState>,
+	call: RuntimeCall,
+) -> Result<RetVal, DispatchError>
+where
+	E: Ext<T = Runtime>,
+{
+	let weight = call.get_dispatch_info().weight;
+	let charged = env.charge_weight(weight)?;
+	let origin = RawOrigin::Signed(env.ext().address().clone()).into();
+	let result = call.dispatch(origin);
+	let actual_weight = match &result {
+		Ok(post_info) => post_info.actual_weight,
+		Err(error) => error.post_info.actual_weight,
+	};
+	if let Some(actual_weight) = actual_weight {
+		env.adjust_weight(charged, actual_weight.min(weight));
+	}
+	Ok(RetVal::Converging(if result.is_ok() { SUCCESS } else { FAILED }))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn function_ids_are_stable() {
+		// deployed contracts hard code these ids, they must never change
+		assert_eq!(Func::try_from(0x0001), Ok(Func::OwnerOf));
+		assert_eq!(Func::try_from(0x0002), Ok(Func::CustodianOf));
+		assert_eq!(Func::try_from(0x0003), Ok(Func::Transfer));
+		assert_eq!(Func::try_from(0x0004), Ok(Func::Approve));
+		assert_eq!(Func::try_from(0x0101), Ok(Func::CreateRental));
+		assert_eq!(Func::try_from(0x0102), Ok(Func::StopRenting));
+		assert_eq!(Func::try_from(0x0103), Ok(Func::Rental));
+		assert_eq!(Func::try_from(0x0104), Ok(Func::Reputation));
+		assert_eq!(Func::try_from(0x0105), Ok(Func::UserOf));
+		assert!(Func::try_from(0x0005).is_err());
+	}
+}
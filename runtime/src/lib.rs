@@ -5,6 +5,7 @@
 // Make the WASM binary available.
 #[cfg(feature = "std")]
 include!(concat!(env!("OUT_DIR"), "/wasm_binary.rs"));
+use codec::Encode;
 use frame_support::dispatch::DispatchClass;
 use pallet_grandpa::{
 	fg_primitives, AuthorityId as GrandpaId, AuthorityList as GrandpaAuthorityList,
@@ -15,9 +16,11 @@ use sp_core::{crypto::KeyTypeId, OpaqueMetadata};
 use sp_runtime::{
 	create_runtime_str, generic, impl_opaque_keys,
 	traits::{
-		AccountIdLookup, BlakeTwo256, Block as BlockT, IdentifyAccount, NumberFor, One, Verify,
+		AccountIdConversion, AccountIdLookup, BlakeTwo256, Block as BlockT,
+		Extrinsic as ExtrinsicT, IdentifyAccount, NumberFor, One, SaturatedConversion,
+		StaticLookup, Verify,
 	},
-	transaction_validity::{TransactionSource, TransactionValidity},
+	transaction_validity::{TransactionPriority, TransactionSource, TransactionValidity},
 	ApplyExtrinsicResult, MultiSignature,
 };
 use sp_std::prelude::*;
@@ -28,17 +31,18 @@ use sp_version::RuntimeVersion;
 pub use frame_support::{
 	construct_runtime, parameter_types,
 	traits::{
-		AsEnsureOriginWithArg, ConstU128, ConstU32, ConstU64, ConstU8, Currency,
-		KeyOwnerProofSystem, Randomness, StorageInfo,
+		AsEnsureOriginWithArg, ConstBool, ConstU128, ConstU32, ConstU64, ConstU8, Contains,
+		Currency, EitherOfDiverse, EqualPrivilegeOnly, Imbalance, KeyOwnerProofSystem,
+		OnUnbalanced, Randomness, StorageInfo,
 	},
 	weights::{
 		constants::{BlockExecutionWeight, ExtrinsicBaseWeight, RocksDbWeight, WEIGHT_PER_SECOND},
 		IdentityFee, Weight,
 	},
-	StorageValue,
+	PalletId, StorageValue,
 };
 pub use frame_system::Call as SystemCall;
-use frame_system::{EnsureRoot, EnsureSigned};
+use frame_system::{EnsureRoot, EnsureSigned, EnsureSignedBy, EnsureWithSuccess};
 //use frame_system::Origin;
 pub use pallet_balances::Call as BalancesCall;
 pub use pallet_timestamp::Call as TimestampCall;
@@ -51,6 +55,9 @@ pub use sp_runtime::{Perbill, Permill};
 pub use pallet_nft_currency;
 pub use pallet_renting;
 
+/// Chain extension giving contracts access to the NFT and renting pallets.
+pub mod chain_extension;
+
 /// An index to a block.
 pub type BlockNumber = u32;
 
@@ -106,7 +113,7 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
 	//   `spec_version`, and `authoring_version` are the same between Wasm and native.
 	// This value is set to 100 to notify Polkadot-JS App (https://polkadot.js.org/apps) to use
 	//   the compatible custom types.
-	spec_version: 100,
+	spec_version: 101,
 	impl_version: 1,
 	apis: RUNTIME_API_VERSIONS,
 	transaction_version: 1,
@@ -273,9 +280,26 @@ parameter_types! {
 	pub FeeMultiplier: Multiplier = Multiplier::one();
 }
 
+type NegativeImbalance = <Balances as Currency<AccountId>>::NegativeImbalance;
+
+/// Burns a fifth of the transaction fees, the rest of the fees and the tips go to the treasury.
+pub struct DealWithFees;
+
+impl OnUnbalanced<NegativeImbalance> for DealWithFees {
+	fn on_unbalanceds<B>(mut fees_then_tips: impl Iterator<Item = NegativeImbalance>) {
+		if let Some(fees) = fees_then_tips.next() {
+			let (mut to_treasury, _burned) = fees.ration(80, 20);
+			if let Some(tips) = fees_then_tips.next() {
+				tips.merge_into(&mut to_treasury);
+			}
+			Treasury::on_unbalanced(to_treasury);
+		}
+	}
+}
+
 impl pallet_transaction_payment::Config for Runtime {
 	type RuntimeEvent = RuntimeEvent;
-	type OnChargeTransaction = CurrencyAdapter<Balances, ()>;
+	type OnChargeTransaction = CurrencyAdapter<Balances, DealWithFees>;
 	type OperationalFeeMultiplier = ConstU8<5>;
 	type WeightToFee = IdentityFee<Balance>;
 	type LengthToFee = IdentityFee<Balance>;
@@ -287,20 +311,260 @@ impl pallet_sudo::Config for Runtime {
 	type RuntimeCall = RuntimeCall;
 }
 
+parameter_types! {
+	pub const PreimageBaseDeposit: Balance = DOLLARS;
+	pub const PreimageByteDeposit: Balance = CENTS;
+}
+
+impl pallet_preimage::Config for Runtime {
+	type WeightInfo = pallet_preimage::weights::SubstrateWeight<Runtime>;
+	type RuntimeEvent = RuntimeEvent;
+	type Currency = Balances;
+	type ManagerOrigin = EnsureRoot<AccountId>;
+	type BaseDeposit = PreimageBaseDeposit;
+	type ByteDeposit = PreimageByteDeposit;
+}
+
+parameter_types! {
+	pub MaximumSchedulerWeight: Weight = Perbill::from_percent(80) *
+		BlockWeights::get().max_block;
+}
+
+impl pallet_scheduler::Config for Runtime {
+	type RuntimeEvent = RuntimeEvent;
+	type RuntimeOrigin = RuntimeOrigin;
+	type PalletsOrigin = OriginCaller;
+	type RuntimeCall = RuntimeCall;
+	type MaximumWeight = MaximumSchedulerWeight;
+	type ScheduleOrigin = EnsureRoot<AccountId>;
+	type MaxScheduledPerBlock = ConstU32<50>;
+	type WeightInfo = pallet_scheduler::weights::SubstrateWeight<Runtime>;
+	type OriginPrivilegeCmp = EqualPrivilegeOnly;
+	type Preimages = Preimage;
+}
+
+parameter_types! {
+	pub const CouncilMotionDuration: BlockNumber = 3 * DAYS;
+	pub const CouncilMaxProposals: u32 = 100;
+	pub const CouncilMaxMembers: u32 = 100;
+}
+
+type CouncilCollective = pallet_collective::Instance1;
+impl pallet_collective::Config<CouncilCollective> for Runtime {
+	type RuntimeOrigin = RuntimeOrigin;
+	type Proposal = RuntimeCall;
+	type RuntimeEvent = RuntimeEvent;
+	type MotionDuration = CouncilMotionDuration;
+	type MaxProposals = CouncilMaxProposals;
+	type MaxMembers = CouncilMaxMembers;
+	type DefaultVote = pallet_collective::PrimeDefaultVote;
+	type WeightInfo = pallet_collective::weights::SubstrateWeight<Runtime>;
+}
+
+/// Root, which referenda and sudo dispatch with, or the council in proportion `N / D`.
+pub type EnsureRootOrCouncil<const N: u32, const D: u32> = EitherOfDiverse<
+	EnsureRoot<AccountId>,
+	pallet_collective::EnsureProportionAtLeast<AccountId, CouncilCollective, N, D>,
+>;
+
+/// Origin of the admin operations of the NFT and renting pallets.
+pub type GovernanceOrigin = EnsureRootOrCouncil<2, 3>;
+
+parameter_types! {
+	pub const LaunchPeriod: BlockNumber = 7 * DAYS;
+	pub const VotingPeriod: BlockNumber = 7 * DAYS;
+	pub const FastTrackVotingPeriod: BlockNumber = 3 * HOURS;
+	pub const EnactmentPeriod: BlockNumber = 2 * DAYS;
+	pub const CooloffPeriod: BlockNumber = 7 * DAYS;
+	pub const MinimumDeposit: Balance = 100 * DOLLARS;
+	pub const MaxProposals: u32 = 100;
+}
+
+impl pallet_democracy::Config for Runtime {
+	type RuntimeEvent = RuntimeEvent;
+	type Currency = Balances;
+	type EnactmentPeriod = EnactmentPeriod;
+	type LaunchPeriod = LaunchPeriod;
+	type VotingPeriod = VotingPeriod;
+	type VoteLockingPeriod = EnactmentPeriod;
+	type MinimumDeposit = MinimumDeposit;
+	/// Half of the council can put a proposal to the next referendum.
+	type ExternalOrigin =
+		pallet_collective::EnsureProportionAtLeast<AccountId, CouncilCollective, 1, 2>;
+	/// Three quarters of the council can have it carried by a simple majority.
+	type ExternalMajorityOrigin =
+		pallet_collective::EnsureProportionAtLeast<AccountId, CouncilCollective, 3, 4>;
+	/// The whole council can have it carried unless a majority votes against.
+	type ExternalDefaultOrigin =
+		pallet_collective::EnsureProportionAtLeast<AccountId, CouncilCollective, 1, 1>;
+	type FastTrackOrigin = EnsureRootOrCouncil<2, 3>;
+	type InstantOrigin = EnsureRootOrCouncil<1, 1>;
+	type InstantAllowed = ConstBool<true>;
+	type FastTrackVotingPeriod = FastTrackVotingPeriod;
+	type CancellationOrigin = EnsureRootOrCouncil<2, 3>;
+	type CancelProposalOrigin = EnsureRootOrCouncil<1, 1>;
+	type BlacklistOrigin = EnsureRoot<AccountId>;
+	/// Any council member can veto a proposal of the council once, for the cool-off period.
+	type VetoOrigin = pallet_collective::EnsureMember<AccountId, CouncilCollective>;
+	type CooloffPeriod = CooloffPeriod;
+	type Slash = Treasury;
+	type Scheduler = Scheduler;
+	type PalletsOrigin = OriginCaller;
+	type MaxVotes = ConstU32<100>;
+	type WeightInfo = pallet_democracy::weights::SubstrateWeight<Runtime>;
+	type MaxProposals = MaxProposals;
+	type Preimages = Preimage;
+	type MaxDeposits = ConstU32<100>;
+	type MaxBlacklisted = ConstU32<100>;
+}
+
+parameter_types! {
+	pub const TreasuryPalletId: PalletId = PalletId(*b"py/trsry");
+	pub const ProposalBond: Permill = Permill::from_percent(5);
+	pub const ProposalBondMinimum: Balance = DOLLARS;
+	pub const SpendPeriod: BlockNumber = DAYS;
+	pub const Burn: Permill = Permill::zero();
+	pub const MaxApprovals: u32 = 100;
+	pub const MaxTreasurySpend: Balance = Balance::MAX;
+}
+
+impl pallet_treasury::Config for Runtime {
+	type PalletId = TreasuryPalletId;
+	type Currency = Balances;
+	type ApproveOrigin = GovernanceOrigin;
+	type RejectOrigin = EnsureRootOrCouncil<1, 2>;
+	type RuntimeEvent = RuntimeEvent;
+	type OnSlash = Treasury;
+	type ProposalBond = ProposalBond;
+	type ProposalBondMinimum = ProposalBondMinimum;
+	type ProposalBondMaximum = ();
+	type SpendPeriod = SpendPeriod;
+	type Burn = Burn;
+	type BurnDestination = ();
+	type SpendFunds = ();
+	type WeightInfo = pallet_treasury::weights::SubstrateWeight<Runtime>;
+	type MaxApprovals = MaxApprovals;
+	// spending without a proposal takes a referendum
+	type SpendOrigin = EnsureWithSuccess<EnsureRoot<AccountId>, AccountId, MaxTreasurySpend>;
+}
+
 /// Configure the pallet-nft_currency in pallets/nft_currency.
 impl pallet_nft_currency::Config for Runtime {
 	type RuntimeEvent = RuntimeEvent;
+	type AdminOrigin = GovernanceOrigin;
 	type Randomness = RandomnessCollectiveFlip;
+	type AuthorityId = pallet_nft_currency::metadata::crypto::MetadataAuthId;
+	type VerifierOrigin = EnsureSignedBy<MetadataVerifiers, AccountId>;
+	type RecheckInterval = ConstU32<HOURS>;
+	type MaxMetadataChecks = ConstU32<8>;
+	type WeightInfo = pallet_nft_currency::weights::SubstrateWeight<Runtime>;
+}
+
+parameter_types! {
+	pub const MaxMetadataVerifiers: u32 = 16;
+}
+
+/// Metadata reports are accepted from the members governance elects, whose nodes hold their
+/// account as an `nftm` key.
+type MetadataVerifiersMembership = pallet_membership::Instance1;
+impl pallet_membership::Config<MetadataVerifiersMembership> for Runtime {
+	type RuntimeEvent = RuntimeEvent;
+	type AddOrigin = GovernanceOrigin;
+	type RemoveOrigin = GovernanceOrigin;
+	type SwapOrigin = GovernanceOrigin;
+	type ResetOrigin = GovernanceOrigin;
+	type PrimeOrigin = GovernanceOrigin;
+	type MembershipInitialized = ();
+	type MembershipChanged = ();
+	type MaxMembers = MaxMetadataVerifiers;
+	type WeightInfo = pallet_membership::weights::SubstrateWeight<Runtime>;
+}
+
+impl frame_system::offchain::SigningTypes for Runtime {
+	type Public = <Signature as Verify>::Signer;
+	type Signature = Signature;
+}
+
+impl<C> frame_system::offchain::CreateSignedTransaction<C> for Runtime
+where
+	RuntimeCall: From<C>,
+{
+	fn create_transaction<A: frame_system::offchain::AppCrypto<Self::Public, Self::Signature>>(
+		call: RuntimeCall,
+		public: <Signature as Verify>::Signer,
+		account: AccountId,
+		nonce: Index,
+	) -> Option<(RuntimeCall, <UncheckedExtrinsic as ExtrinsicT>::SignaturePayload)> {
+		// the transaction is valid for a short period after the current block
+		let period =
+			BlockHashCount::get().checked_next_power_of_two().map(|c| c / 2).unwrap_or(2) as u64;
+		let current_block = System::block_number().saturated_into::<u64>().saturating_sub(1);
+		let extra: SignedExtra = (
+			frame_system::CheckNonZeroSender::<Runtime>::new(),
+			frame_system::CheckSpecVersion::<Runtime>::new(),
+			frame_system::CheckTxVersion::<Runtime>::new(),
+			frame_system::CheckGenesis::<Runtime>::new(),
+			frame_system::CheckEra::<Runtime>::from(generic::Era::mortal(period, current_block)),
+			frame_system::CheckNonce::<Runtime>::from(nonce),
+			frame_system::CheckWeight::<Runtime>::new(),
+			pallet_transaction_payment::ChargeTransactionPayment::<Runtime>::from(0),
+		);
+		let raw_payload = SignedPayload::new(call, extra).ok()?;
+		let signature = raw_payload.using_encoded(|payload| A::sign(payload, public))?;
+		let address = AccountIdLookup::<AccountId, ()>::unlookup(account);
+		let (call, extra, _) = raw_payload.deconstruct();
+		Some((call, (address, signature, extra)))
+	}
+}
+
+parameter_types! {
+	pub const RentingPalletId: PalletId = PalletId(*b"py/rentg");
+	// the marketplace cut of rental payments funds the treasury
+	pub MarketplaceFeeAccount: AccountId = TreasuryPalletId::get().into_account_truncating();
+	pub const DefaultMarketplaceFee: Permill = Permill::from_percent(2);
+	pub const RentalGracePeriod: BlockNumber = 6 * HOURS;
+	pub const RentalLateFee: Permill = Permill::from_percent(5);
+	pub const RentalDisputeDeposit: u64 = DOLLARS as u64;
+	pub const RentalCleanupPriority: TransactionPriority = TransactionPriority::max_value() / 2;
 }
 
 impl pallet_renting::Config for Runtime {
 	type RuntimeEvent = RuntimeEvent;
+	type RuntimeCall = RuntimeCall;
+	type PalletsOrigin = OriginCaller;
+	type Scheduler = Scheduler;
+	type Preimages = Preimage;
 	type Randomness = RandomnessCollectiveFlip;
 	type TokenNFT = NftCurrency;
+	type Uniques = pallet_renting::UniquesItems<Runtime>;
 	type Signature = sp_core::ecdsa::Signature;
 	type PublicKey = sp_core::ecdsa::Public;
 	type Timestamp = pallet_timestamp::Pallet<Runtime>;
+	type BlockTime = ConstU64<MILLISECS_PER_BLOCK>;
 	type Currency = Balances;
+	type Assets = Assets;
+	type FeeDestination = MarketplaceFeeAccount;
+	type DefaultMarketplaceFee = DefaultMarketplaceFee;
+	type GracePeriod = RentalGracePeriod;
+	type MaxPaymentRetries = ConstU32<3>;
+	type LateFee = RentalLateFee;
+	type AdminOrigin = GovernanceOrigin;
+	// disputes only need a simple majority of the council to be settled quickly
+	type ArbiterOrigin = EnsureRootOrCouncil<1, 2>;
+	type DisputeDeposit = RentalDisputeDeposit;
+	type PalletId = RentingPalletId;
+	type CleanupInterval = ConstU32<{ 10 * MINUTES }>;
+	type MaxCleanup = ConstU32<16>;
+	type UnsignedPriority = RentalCleanupPriority;
+	type WeightInfo = pallet_renting::weights::SubstrateWeight<Runtime>;
+}
+
+impl<C> frame_system::offchain::SendTransactionTypes<C> for Runtime
+where
+	RuntimeCall: From<C>,
+{
+	type OverarchingCall = RuntimeCall;
+	type Extrinsic = UncheckedExtrinsic;
 }
 parameter_types! {
 	pub const CollectionDeposit: Balance = 10 * CENTS;
@@ -323,7 +587,8 @@ impl pallet_uniques::Config for Runtime {
 	type Currency = Balances;
 	type ForceOrigin = EnsureRoot<AccountId>;
 	type CreateOrigin = AsEnsureOriginWithArg<EnsureSigned<AccountId>>;
-	type Locker = ();
+	// rented items stay with their owner but cannot be transferred
+	type Locker = Renting;
 	type CollectionDeposit = CollectionDeposit;
 	type ItemDeposit = ItemDeposit;
 	type MetadataDepositBase = UniquesMetadataDepositBase;
@@ -373,25 +638,49 @@ parameter_types! {
 	pub Schedule: pallet_contracts::Schedule<Runtime> = Default::default();
 }
 
+/// Calls contracts may dispatch with `seal_call_runtime`. Deployed contracts depend on them, so
+/// calls are only ever added to this list and the listed calls keep their signature.
+pub struct ContractsCallFilter;
+
+impl Contains<RuntimeCall> for ContractsCallFilter {
+	fn contains(call: &RuntimeCall) -> bool {
+		match call {
+			RuntimeCall::Balances(pallet_balances::Call::transfer { .. }) => true,
+			RuntimeCall::NftCurrency(call) => matches!(
+				call,
+				pallet_nft_currency::Call::transfer_ownership { .. }
+					| pallet_nft_currency::Call::safe_transfer_ownership { .. }
+					| pallet_nft_currency::Call::approve { .. }
+					| pallet_nft_currency::Call::approve_for_all { .. }
+			),
+			RuntimeCall::Renting(call) => matches!(
+				call,
+				pallet_renting::Call::create_rental { .. }
+					| pallet_renting::Call::cancel_offer { .. }
+					| pallet_renting::Call::stop_renting { .. }
+					| pallet_renting::Call::extend_rental { .. }
+			),
+			_ => false,
+		}
+	}
+}
+
 impl pallet_contracts::Config for Runtime {
 	type Time = Timestamp;
 	type Randomness = RandomnessCollectiveFlip;
 	type Currency = Balances;
 	type RuntimeEvent = RuntimeEvent;
 	type RuntimeCall = RuntimeCall;
-	/// The safest default is to allow no calls at all.
-	///
-	/// Runtimes should whitelist dispatchables that are allowed to be called from contracts
-	/// and make sure they are stable. Dispatchables exposed to contracts are not allowed to
-	/// change because that would break already deployed contracts. The `RuntimeCall` structure
-	/// itself is not allowed to change the indices of existing pallets, too.
-	type CallFilter = frame_support::traits::Nothing;
+	/// Dispatchables exposed to contracts are not allowed to change because that would break
+	/// already deployed contracts. The `RuntimeCall` structure itself is not allowed to change
+	/// the indices of existing pallets, too.
+	type CallFilter = ContractsCallFilter;
 	type DepositPerItem = DepositPerItem;
 	type DepositPerByte = DepositPerBytes;
 	type CallStack = [pallet_contracts::Frame<Self>; 31];
 	type WeightPrice = pallet_transaction_payment::Pallet<Self>;
 	type WeightInfo = pallet_contracts::weights::SubstrateWeight<Self>;
-	type ChainExtension = pallet_assets_chain_extension::substrate::AssetsExtension;
+	type ChainExtension = chain_extension::NftRentingExtension;
 	type DeletionQueueDepth = DeletionQueueDepth;
 	type DeletionWeightLimit = DeletionWeightLimit;
 	type Schedule = Schedule;
@@ -429,6 +718,12 @@ construct_runtime!(
 		Uniques : pallet_uniques::{Pallet, Call, Storage, Event<T>},
 		Contracts: pallet_contracts,
 		Assets: pallet_assets,
+		Preimage: pallet_preimage,
+		Scheduler: pallet_scheduler,
+		Council: pallet_collective::<Instance1>,
+		Democracy: pallet_democracy,
+		Treasury: pallet_treasury,
+		MetadataVerifiers: pallet_membership::<Instance1>,
 	}
 );
 
@@ -454,6 +749,8 @@ pub type UncheckedExtrinsic =
 	generic::UncheckedExtrinsic<Address, RuntimeCall, Signature, SignedExtra>;
 /// The payload being signed in transactions.
 pub type SignedPayload = generic::SignedPayload<RuntimeCall, SignedExtra>;
+/// Storage migrations run on the next runtime upgrade.
+pub type Migrations = (pallet_renting::migration::v1::MigrateToV1<Runtime>,);
 /// Executive: handles dispatch to the various modules.
 pub type Executive = frame_executive::Executive<
 	Runtime,
@@ -461,6 +758,7 @@ pub type Executive = frame_executive::Executive<
 	frame_system::ChainContext<Runtime>,
 	Runtime,
 	AllPalletsWithSystem,
+	Migrations,
 >;
 
 #[cfg(feature = "runtime-benchmarks")]
@@ -474,7 +772,12 @@ mod benches {
 		[frame_system, SystemBench::<Runtime>]
 		[pallet_balances, Balances]
 		[pallet_timestamp, Timestamp]
+		[pallet_collective, Council]
+		[pallet_democracy, Democracy]
+		[pallet_treasury, Treasury]
+		[pallet_membership, MetadataVerifiers]
 		[pallet_nft_currency, NftCurrency]
+		[pallet_renting, Renting]
 	);
 }
 
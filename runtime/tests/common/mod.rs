@@ -0,0 +1,96 @@
+//! Accounts, genesis and order helpers shared by the runtime integration tests.
+
+#![allow(dead_code)]
+
+use frame_support::{traits::Get, weights::Weight};
+use node_template_runtime::{
+	AccountId, BalancesConfig, BuildStorage, Contracts, GenesisConfig, MarketplaceFeeAccount,
+	NftCurrency, Runtime, RuntimeOrigin, System, DOLLARS,
+};
+use pallet_contracts_primitives::Code;
+use sp_core::{crypto::Ss58Codec, sr25519, Pair};
+
+pub const GAS_LIMIT: Weight = Weight::from_ref_time(100_000_000_000);
+/// Unix time of the first block, in milliseconds.
+pub const GENESIS_TIME: u64 = 1_700_000_000_000;
+pub const FEE: u64 = DOLLARS as u64;
+
+pub fn pair(seed: &str) -> sr25519::Pair {
+	sr25519::Pair::from_string(&format!("//{}", seed), None).expect("static seed is valid")
+}
+
+pub fn account(pair: &sr25519::Pair) -> AccountId {
+	pair.public().into()
+}
+
+/// Alice and Bob endowed, at block 1 and `GENESIS_TIME`.
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	let storage = GenesisConfig {
+		balances: BalancesConfig {
+			balances: vec![
+				(account(&pair("Alice")), 1_000 * DOLLARS),
+				(account(&pair("Bob")), 1_000 * DOLLARS),
+				(MarketplaceFeeAccount::get(), DOLLARS),
+			],
+		},
+		..Default::default()
+	}
+	.build_storage()
+	.unwrap();
+	let mut ext = sp_io::TestExternalities::new(storage);
+	ext.execute_with(|| {
+		System::set_block_number(1);
+		pallet_timestamp::Pallet::<Runtime>::set_timestamp(GENESIS_TIME);
+	});
+	ext
+}
+
+/// Deploy the contract of the `wat` fixture, instantiated by Alice.
+pub fn deploy(wat: &str) -> AccountId {
+	let wasm = wat::parse_str(wat).unwrap();
+	Contracts::bare_instantiate(
+		account(&pair("Alice")),
+		0,
+		GAS_LIMIT,
+		None,
+		Code::Upload(wasm),
+		vec![],
+		vec![],
+		false,
+	)
+	.result
+	.expect("the fixture instantiates")
+	.account_id
+}
+
+/// Token minted by Alice to `owner`.
+pub fn mint_to(owner: &AccountId) -> Vec<u8> {
+	frame_support::assert_ok!(NftCurrency::mint_to(
+		RuntimeOrigin::signed(account(&pair("Alice"))),
+		owner.clone(),
+		b"uri".to_vec()
+	));
+	NftCurrency::list_owned(owner.clone()).last().cloned().unwrap()
+}
+
+/// Order of `lender` renting out `token` for `FEE`, or of `borrower` taking it when set.
+pub fn order(
+	lender: &AccountId,
+	borrower: Option<&AccountId>,
+	token: &[u8],
+	due_date: u64,
+) -> Vec<u8> {
+	let borrower = borrower
+		.map(|borrower| format!("\"borrower\":\"{}\",", borrower.to_ss58check()))
+		.unwrap_or_default();
+	let token = token.iter().map(|b| format!("{:02x}", b)).collect::<String>();
+	format!(
+		"{{\"lender\":\"{}\",{}\"fee\":{},\"token\":\"{}\",\"due_date\":{}}}",
+		lender.to_ss58check(),
+		borrower,
+		FEE,
+		token,
+		due_date
+	)
+	.into_bytes()
+}
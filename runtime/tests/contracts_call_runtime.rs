@@ -0,0 +1,86 @@
+//! A contract drives a rental through `seal_call_runtime`, within the calls `ContractsCallFilter`
+//! lets through.
+
+mod common;
+
+use codec::{Decode, Encode};
+use common::*;
+use node_template_runtime::{
+	AccountId, Balances, Contracts, NftCurrency, Renting, RuntimeCall, MILLISECS_PER_BLOCK,
+};
+use pallet_nft_currency::NonFungibleToken;
+use sp_core::Pair;
+use sp_runtime::Permill;
+
+/// Deploy the contract dispatching the call it is given.
+fn deploy() -> AccountId {
+	common::deploy(include_str!("fixtures/call_runtime.wat"))
+}
+
+/// Status code of `seal_call_runtime` when `contract` dispatches `call`.
+fn call_runtime(contract: &AccountId, call: RuntimeCall) -> u32 {
+	let result = Contracts::bare_call(
+		account(&pair("Alice")),
+		contract.clone(),
+		0,
+		GAS_LIMIT,
+		None,
+		call.encode(),
+		false,
+	)
+	.result
+	.expect("the fixture does not trap");
+	u32::decode(&mut &result.data[..]).unwrap()
+}
+
+#[test]
+fn contract_rents_out_its_token() {
+	new_test_ext().execute_with(|| {
+		let contract = deploy();
+		let bob = pair("Bob");
+		let token = mint_to(&contract);
+
+		// the contract is the lender and submits the match, only the borrower signs
+		let due_date = (GENESIS_TIME + 100 * MILLISECS_PER_BLOCK) / 1000;
+		let message_left = order(&contract, None, &token, due_date);
+		let message_right = order(&contract, Some(&account(&bob)), &token, due_date);
+		let create_rental = pallet_renting::Call::create_rental {
+			lender: contract.clone(),
+			borrower: account(&bob),
+			message_left,
+			signature_left: vec![],
+			signature_right: bob.sign(&message_right).0.to_vec(),
+			message_right,
+		};
+		let contract_before = Balances::free_balance(&contract);
+		assert_eq!(call_runtime(&contract, create_rental.into()), 0);
+		assert_eq!(NftCurrency::custodian_of_token(token.clone()), Some(account(&bob)));
+		assert!(Renting::rental(&account(&bob), &token).is_some());
+
+		// the contract pays the rent it earned onwards
+		let earned = Balances::free_balance(&contract) - contract_before;
+		assert_eq!(earned, (FEE - Permill::from_percent(2).mul_floor(FEE)) as u128);
+		let alice_before = Balances::free_balance(account(&pair("Alice")));
+		let transfer =
+			pallet_balances::Call::transfer { dest: account(&pair("Alice")).into(), value: earned };
+		assert_eq!(call_runtime(&contract, transfer.into()), 0);
+		assert_eq!(Balances::free_balance(account(&pair("Alice"))), alice_before + earned);
+	});
+}
+
+#[test]
+fn calls_outside_the_filter_are_refused() {
+	new_test_ext().execute_with(|| {
+		let contract = deploy();
+		let set_fee = pallet_renting::Call::set_marketplace_fee { fee: Permill::zero() };
+		assert_ne!(call_runtime(&contract, set_fee.into()), 0);
+		assert_eq!(Renting::marketplace_fee(), Permill::from_percent(2));
+
+		let mint = pallet_nft_currency::Call::mint_to { to: contract.clone(), token_uri: vec![] };
+		assert_ne!(call_runtime(&contract, mint.into()), 0);
+		assert!(NftCurrency::list_owned(contract.clone()).is_empty());
+
+		let remark = frame_system::Call::remark { remark: vec![] };
+		assert_ne!(call_runtime(&contract, remark.into()), 0);
+	});
+}
@@ -0,0 +1,148 @@
+//! A contract calls the NFT and renting chain extension: queries answer from storage, calls are
+//! dispatched with the contract as origin and charged the weight of their extrinsic.
+
+mod common;
+
+use codec::{Decode, Encode};
+use common::*;
+use frame_support::{dispatch::GetDispatchInfo, traits::Get, weights::Weight};
+use node_template_runtime::{
+	chain_extension::{FAILED, SUCCESS},
+	AccountId, Contracts, NftCurrency, Runtime, RuntimeCall, MILLISECS_PER_BLOCK,
+};
+use pallet_nft_currency::NonFungibleToken;
+use pallet_renting::Reputation;
+use sp_core::Pair;
+
+const OWNER_OF: u32 = 0x4e52_0001;
+const CUSTODIAN_OF: u32 = 0x4e52_0002;
+const TRANSFER: u32 = 0x4e52_0003;
+const CREATE_RENTAL: u32 = 0x4e52_0101;
+const RENTAL: u32 = 0x4e52_0103;
+const REPUTATION: u32 = 0x4e52_0104;
+const USER_OF: u32 = 0x4e52_0105;
+
+/// Deploy the contract calling the extension function it is given.
+fn deploy() -> AccountId {
+	common::deploy(include_str!("fixtures/chain_extension.wat"))
+}
+
+/// Status code and output of the extension function `func_id` called by `contract` with `input`,
+/// and the gas the contract call consumed.
+fn call_extension(
+	contract: &AccountId,
+	func_id: u32,
+	input: impl Encode,
+) -> (u32, Vec<u8>, Weight) {
+	let mut data = func_id.to_le_bytes().to_vec();
+	data.extend(input.encode());
+	let result = Contracts::bare_call(
+		account(&pair("Alice")),
+		contract.clone(),
+		0,
+		GAS_LIMIT,
+		None,
+		data,
+		false,
+	);
+	let output = result.result.expect("the fixture does not trap").data;
+	let status = u32::decode(&mut &output[..4]).unwrap();
+	(status, output[4..].to_vec(), result.gas_consumed)
+}
+
+/// Decoded output of the query `func_id` of `contract` with `input`.
+fn query<T: Decode>(contract: &AccountId, func_id: u32, input: impl Encode) -> T {
+	let (status, output, _) = call_extension(contract, func_id, input);
+	assert_eq!(status, SUCCESS);
+	T::decode(&mut &output[..]).unwrap()
+}
+
+#[test]
+fn queries_answer_from_storage() {
+	new_test_ext().execute_with(|| {
+		let contract = deploy();
+		let bob = pair("Bob");
+		let token = mint_to(&contract);
+		assert_eq!(query::<Option<AccountId>>(&contract, OWNER_OF, &token), Some(contract.clone()));
+		assert_eq!(
+			query::<Option<AccountId>>(&contract, CUSTODIAN_OF, &token),
+			Some(contract.clone())
+		);
+		assert_eq!(
+			query::<Reputation>(&contract, REPUTATION, account(&bob)),
+			Reputation::default()
+		);
+		type RentalInfo = Option<(AccountId, u64, u64, Option<u32>)>;
+		assert_eq!(query::<RentalInfo>(&contract, RENTAL, (account(&bob), &token)), None);
+
+		// the contract is the lender and submits the match, only the borrower signs
+		let due_date = (GENESIS_TIME + 100 * MILLISECS_PER_BLOCK) / 1000;
+		let message_right = order(&contract, Some(&account(&bob)), &token, due_date);
+		let input = (
+			contract.clone(),
+			account(&bob),
+			order(&contract, None, &token, due_date),
+			Vec::<u8>::new(),
+			message_right.clone(),
+			bob.sign(&message_right).0.to_vec(),
+		);
+		assert_eq!(call_extension(&contract, CREATE_RENTAL, input).0, SUCCESS);
+
+		assert_eq!(
+			query::<RentalInfo>(&contract, RENTAL, (account(&bob), &token)),
+			Some((contract.clone(), FEE, due_date, None))
+		);
+		assert_eq!(
+			query::<Option<AccountId>>(&contract, CUSTODIAN_OF, &token),
+			Some(account(&bob))
+		);
+		assert_eq!(query::<Option<AccountId>>(&contract, USER_OF, &token), Some(account(&bob)));
+	});
+}
+
+#[test]
+fn calls_are_charged_the_weight_of_their_extrinsic() {
+	new_test_ext().execute_with(|| {
+		let contract = deploy();
+		let alice = account(&pair("Alice"));
+		let token = mint_to(&contract);
+		let call: RuntimeCall = pallet_nft_currency::Call::transfer_ownership {
+			to: alice.clone(),
+			token_id: token.clone(),
+		}
+		.into();
+		let weight = call.get_dispatch_info().weight;
+		let reads = <Runtime as frame_system::Config>::DbWeight::get().reads(1);
+
+		let (_, _, query_gas) = call_extension(&contract, OWNER_OF, &token);
+		let (status, _, transfer_gas) = call_extension(&contract, TRANSFER, (&alice, &token));
+		assert_eq!(status, SUCCESS);
+		assert_eq!(NftCurrency::owner_of_token(token.clone()), Some(alice.clone()));
+		// both contract calls pay the same overhead, the query a storage read on top
+		assert!(
+			transfer_gas.ref_time() >= query_gas.ref_time() + weight.ref_time() - reads.ref_time()
+		);
+
+		// the token is gone, the dispatch fails without trapping the contract and is still paid
+		let (status, _, failed_gas) = call_extension(&contract, TRANSFER, (&alice, &token));
+		assert_eq!(status, FAILED);
+		assert!(failed_gas.ref_time() >= weight.ref_time());
+	});
+}
+
+#[test]
+fn unknown_functions_trap_the_contract() {
+	new_test_ext().execute_with(|| {
+		let contract = deploy();
+		let result = Contracts::bare_call(
+			account(&pair("Alice")),
+			contract,
+			0,
+			GAS_LIMIT,
+			None,
+			0x4e52_0fffu32.to_le_bytes().to_vec(),
+			false,
+		);
+		assert!(result.result.is_err());
+	});
+}
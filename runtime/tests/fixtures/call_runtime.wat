@@ -0,0 +1,23 @@
+;; Dispatches the SCALE encoded `RuntimeCall` given as input and returns the status code of
+;; `seal_call_runtime` as a little endian u32.
+(module
+	(import "seal0" "seal_input" (func $seal_input (param i32 i32)))
+	(import "seal0" "seal_return" (func $seal_return (param i32 i32 i32)))
+	(import "__unstable__" "seal_call_runtime" (func $seal_call_runtime (param i32 i32) (result i32)))
+	(import "env" "memory" (memory 1 1))
+
+	;; [0, 4) size of the input buffer, then the status code
+	;; [4, 4100) input buffer
+
+	(func (export "deploy"))
+
+	(func (export "call")
+		(i32.store (i32.const 0) (i32.const 4096))
+		(call $seal_input (i32.const 4) (i32.const 0))
+		(i32.store
+			(i32.const 0)
+			(call $seal_call_runtime (i32.const 4) (i32.load (i32.const 0)))
+		)
+		(call $seal_return (i32.const 0) (i32.const 0) (i32.const 4))
+	)
+)
@@ -0,0 +1,40 @@
+;; Calls the chain extension function whose id is the first four bytes of the input with the rest
+;; of the input, and returns the status code as a little endian u32 followed by the output. Only
+;; queries write an output, after a dispatch the output buffer is returned as it is.
+(module
+	(import "seal0" "seal_input" (func $seal_input (param i32 i32)))
+	(import "seal0" "seal_return" (func $seal_return (param i32 i32 i32)))
+	(import "seal0" "seal_call_chain_extension"
+		(func $seal_call_chain_extension (param i32 i32 i32 i32 i32) (result i32))
+	)
+	(import "env" "memory" (memory 1 1))
+
+	;; [0, 4) size of the input buffer
+	;; [4, 4100) input buffer, the function id then its input
+	;; [4100, 4104) size of the output buffer
+	;; [4104, 4108) status code
+	;; [4108, 8204) output buffer
+
+	(func (export "deploy"))
+
+	(func (export "call")
+		(i32.store (i32.const 0) (i32.const 4096))
+		(call $seal_input (i32.const 4) (i32.const 0))
+		(i32.store (i32.const 4100) (i32.const 4096))
+		(i32.store
+			(i32.const 4104)
+			(call $seal_call_chain_extension
+				(i32.load (i32.const 4))
+				(i32.const 8)
+				(i32.sub (i32.load (i32.const 0)) (i32.const 4))
+				(i32.const 4108)
+				(i32.const 4100)
+			)
+		)
+		(call $seal_return
+			(i32.const 0)
+			(i32.const 4104)
+			(i32.add (i32.load (i32.const 4100)) (i32.const 4))
+		)
+	)
+)
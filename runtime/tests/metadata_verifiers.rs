@@ -0,0 +1,42 @@
+//! Metadata reports are accepted from the verifiers governance elects.
+
+use frame_support::traits::EnsureOrigin;
+use node_template_runtime::{
+	AccountId, BuildStorage, GenesisConfig, MetadataVerifiers, Runtime, RuntimeOrigin,
+};
+
+type VerifierOrigin = <Runtime as pallet_nft_currency::Config>::VerifierOrigin;
+
+fn new_test_ext() -> sp_io::TestExternalities {
+	let storage = GenesisConfig::default().build_storage().unwrap();
+	sp_io::TestExternalities::new(storage)
+}
+
+#[test]
+fn only_elected_verifiers_report_metadata() {
+	new_test_ext().execute_with(|| {
+		let verifier = AccountId::from([1; 32]);
+		assert!(VerifierOrigin::ensure_origin(RuntimeOrigin::signed(verifier.clone())).is_err());
+
+		// a signed account cannot elect itself, governance does
+		assert!(MetadataVerifiers::add_member(
+			RuntimeOrigin::signed(verifier.clone()),
+			verifier.clone().into()
+		)
+		.is_err());
+		frame_support::assert_ok!(MetadataVerifiers::add_member(
+			RuntimeOrigin::root(),
+			verifier.clone().into()
+		));
+		assert_eq!(
+			VerifierOrigin::ensure_origin(RuntimeOrigin::signed(verifier.clone())).ok(),
+			Some(verifier.clone())
+		);
+
+		frame_support::assert_ok!(MetadataVerifiers::remove_member(
+			RuntimeOrigin::root(),
+			verifier.clone().into()
+		));
+		assert!(VerifierOrigin::ensure_origin(RuntimeOrigin::signed(verifier)).is_err());
+	});
+}
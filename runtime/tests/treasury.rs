@@ -0,0 +1,51 @@
+//! Transaction fees and the marketplace cut of rental payments end up in the treasury.
+
+mod common;
+
+use common::*;
+use frame_support::traits::{Currency, OnUnbalanced};
+use node_template_runtime::{
+	Balances, DealWithFees, Renting, RuntimeOrigin, Treasury, DOLLARS, MILLISECS_PER_BLOCK,
+};
+use sp_core::Pair;
+use sp_runtime::Permill;
+
+#[test]
+fn marketplace_cut_of_a_rental_goes_to_the_treasury() {
+	new_test_ext().execute_with(|| {
+		let (alice, bob) = (pair("Alice"), pair("Bob"));
+		let token = mint_to(&account(&alice));
+		let due_date = (GENESIS_TIME + 100 * MILLISECS_PER_BLOCK) / 1000;
+		let message_left = order(&account(&alice), None, &token, due_date);
+		let message_right = order(&account(&alice), Some(&account(&bob)), &token, due_date);
+
+		let pot = Balances::free_balance(Treasury::account_id());
+		frame_support::assert_ok!(Renting::create_rental(
+			RuntimeOrigin::signed(account(&bob)),
+			account(&alice),
+			account(&bob),
+			message_left.clone(),
+			alice.sign(&message_left).0.to_vec(),
+			message_right.clone(),
+			bob.sign(&message_right).0.to_vec(),
+		));
+
+		let cut = Permill::from_percent(2).mul_floor(FEE) as u128;
+		assert_eq!(Balances::free_balance(Treasury::account_id()), pot + cut);
+	});
+}
+
+#[test]
+fn treasury_keeps_most_fees_and_all_tips() {
+	new_test_ext().execute_with(|| {
+		let pot = Balances::free_balance(Treasury::account_id());
+		let issuance = Balances::total_issuance();
+		let fees = Balances::issue(10 * DOLLARS);
+		let tips = Balances::issue(DOLLARS);
+		DealWithFees::on_unbalanceds(vec![fees, tips].into_iter());
+
+		assert_eq!(Balances::free_balance(Treasury::account_id()), pot + 9 * DOLLARS);
+		// the fifth of the fees that is not kept is burned
+		assert_eq!(Balances::total_issuance(), issuance + 9 * DOLLARS);
+	});
+}
@@ -0,0 +1,26 @@
+#!/usr/bin/env bash
+# Benchmark the custom pallets and write their weights.rs from the frame weight template.
+# Usage: ./scripts/benchmark.sh [pallet_renting|pallet_nft_currency ...]
+set -e
+
+cd "$(dirname "$0")/.."
+
+pallets=("$@")
+if [ ${#pallets[@]} -eq 0 ]; then
+	pallets=(pallet_nft_currency pallet_renting)
+fi
+
+cargo build --release -p node-template --features runtime-benchmarks
+
+for pallet in "${pallets[@]}"; do
+	./target/release/node-template benchmark pallet \
+		--chain=dev \
+		--steps=50 \
+		--repeat=20 \
+		--pallet="$pallet" \
+		--extrinsic='*' \
+		--execution=wasm \
+		--wasm-execution=compiled \
+		--template=./.maintain/frame-weight-template.hbs \
+		--output="./pallets/${pallet#pallet_}/src/weights.rs"
+done